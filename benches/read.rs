@@ -0,0 +1,58 @@
+//! compare read paths of digestutil with a file larger than read buffers.
+//! xxh3 is used so that the cost of reading dominates hashing.
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use hast::digestutil::{self, ReadOptions};
+use hast::Algorithm;
+use std::io::Write;
+
+const FILE_SIZE: usize = 64 * 1024 * 1024;
+
+fn create_input() -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("hast-bench-{}.bin", std::process::id()));
+    let mut f = std::fs::File::create(&path).unwrap();
+    // non-repeating content, so that nothing is skipped by the storage layer
+    let mut state: u64 = 0x9e3779b97f4a7c15;
+    let mut buf = vec![0u8; 1024 * 1024];
+    for _ in 0..FILE_SIZE / buf.len() {
+        for chunk in buf.chunks_mut(8) {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            chunk.copy_from_slice(&state.to_le_bytes());
+        }
+        f.write_all(&buf).unwrap();
+    }
+    path
+}
+
+fn bench_read(c: &mut Criterion) {
+    let path = create_input();
+    let inputfile = path.to_str().unwrap().to_owned();
+    let algorithm = Algorithm::from_name("xxh3-64", 0).unwrap();
+    let cases = [
+        // same as the previous fixed 1 KiB buffer
+        ("buffer 1K", ReadOptions { buffer_size: 1024, mmap: false, read_ahead: false }),
+        ("buffer 64K", ReadOptions::default()),
+        ("buffer 1M", ReadOptions { buffer_size: 1024 * 1024, mmap: false, read_ahead: false }),
+        ("read-ahead 64K", ReadOptions { read_ahead: true, ..ReadOptions::default() }),
+        ("read-ahead 1M", ReadOptions { buffer_size: 1024 * 1024, mmap: false, read_ahead: true }),
+        ("mmap", ReadOptions { mmap: true, ..ReadOptions::default() }),
+    ];
+    let mut group = c.benchmark_group("read");
+    group.throughput(Throughput::Bytes(FILE_SIZE as u64));
+    group.sample_size(10);
+    for (name, options) in cases.iter() {
+        group.bench_function(*name, |b| {
+            b.iter(|| {
+                let mut d = algorithm.create_hasher().unwrap();
+                digestutil::update_digest_from_path(&mut d, &inputfile, options).unwrap();
+                d.finalize_reset()
+            })
+        });
+    }
+    group.finish();
+    let _ = std::fs::remove_file(&path);
+}
+
+criterion_group!(benches, bench_read);
+criterion_main!(benches);
@@ -0,0 +1,1188 @@
+use super::digestutil;
+use super::error::ApplicationError;
+use digest::Digest;
+use hmac::{Mac, NewMac};
+
+pub trait Hasher {
+    fn update(&mut self, data: &[u8]);
+    fn finalize_reset(&mut self) -> Vec<u8>;
+}
+
+struct FixedOutputHasher<D>(D);
+
+impl<D> Hasher for FixedOutputHasher<D>
+where
+    D: Digest,
+{
+    fn update(&mut self, data: &[u8]) {
+        Digest::update(&mut self.0, data);
+    }
+    fn finalize_reset(&mut self) -> Vec<u8> {
+        self.0.finalize_reset().to_vec()
+    }
+}
+
+struct ExtendableOutputHasher<D> {
+    d: D,
+    outputsize: usize,
+}
+
+impl<D> Hasher for ExtendableOutputHasher<D>
+where
+    D: digest::ExtendableOutput + digest::Update,
+{
+    fn update(&mut self, data: &[u8]) {
+        digest::Update::update(&mut self.d, data);
+    }
+    fn finalize_reset(&mut self) -> Vec<u8> {
+        self.d.finalize_boxed_reset(self.outputsize).to_vec()
+    }
+}
+
+static CRC32: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+static CRC32C: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISCSI);
+static CRC64: crc::Crc<u64> = crc::Crc::<u64>::new(&crc::CRC_64_XZ);
+
+/// checksums are output in big endian, same as zlib and cksum
+struct Crc32Hasher {
+    crc: &'static crc::Crc<u32>,
+    d: crc::Digest<'static, u32>,
+}
+
+impl Hasher for Crc32Hasher {
+    fn update(&mut self, data: &[u8]) {
+        self.d.update(data);
+    }
+    fn finalize_reset(&mut self) -> Vec<u8> {
+        let d = std::mem::replace(&mut self.d, self.crc.digest());
+        d.finalize().to_be_bytes().to_vec()
+    }
+}
+
+struct Crc64Hasher {
+    crc: &'static crc::Crc<u64>,
+    d: crc::Digest<'static, u64>,
+}
+
+impl Hasher for Crc64Hasher {
+    fn update(&mut self, data: &[u8]) {
+        self.d.update(data);
+    }
+    fn finalize_reset(&mut self) -> Vec<u8> {
+        let d = std::mem::replace(&mut self.d, self.crc.digest());
+        d.finalize().to_be_bytes().to_vec()
+    }
+}
+
+struct Adler32Hasher(adler::Adler32);
+
+impl Hasher for Adler32Hasher {
+    fn update(&mut self, data: &[u8]) {
+        self.0.write_slice(data);
+    }
+    fn finalize_reset(&mut self) -> Vec<u8> {
+        let d = std::mem::take(&mut self.0);
+        d.checksum().to_be_bytes().to_vec()
+    }
+}
+
+/// xxhash results are output in canonical(big endian) form, same as xxhsum
+struct Xxh32Hasher {
+    h: xxhash_rust::xxh32::Xxh32,
+    seed: u32,
+}
+
+impl Hasher for Xxh32Hasher {
+    fn update(&mut self, data: &[u8]) {
+        self.h.update(data);
+    }
+    fn finalize_reset(&mut self) -> Vec<u8> {
+        let ret = self.h.digest();
+        self.h.reset(self.seed);
+        ret.to_be_bytes().to_vec()
+    }
+}
+
+struct Xxh64Hasher {
+    h: xxhash_rust::xxh64::Xxh64,
+    seed: u64,
+}
+
+impl Hasher for Xxh64Hasher {
+    fn update(&mut self, data: &[u8]) {
+        self.h.update(data);
+    }
+    fn finalize_reset(&mut self) -> Vec<u8> {
+        let ret = self.h.digest();
+        self.h.reset(self.seed);
+        ret.to_be_bytes().to_vec()
+    }
+}
+
+struct Xxh3Hasher {
+    h: xxhash_rust::xxh3::Xxh3,
+    is128: bool,
+}
+
+impl Hasher for Xxh3Hasher {
+    fn update(&mut self, data: &[u8]) {
+        self.h.update(data);
+    }
+    fn finalize_reset(&mut self) -> Vec<u8> {
+        let ret = if self.is128 {
+            self.h.digest128().to_be_bytes().to_vec()
+        } else {
+            self.h.digest().to_be_bytes().to_vec()
+        };
+        self.h.reset();
+        ret
+    }
+}
+
+struct HmacHasher<D>(D);
+
+impl<D> Hasher for HmacHasher<D>
+where
+    D: Mac,
+{
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+    fn finalize_reset(&mut self) -> Vec<u8> {
+        self.0.finalize_reset().into_bytes().to_vec()
+    }
+}
+
+fn create_hmac_hasher<D>(key: &[u8]) -> Box<dyn Hasher>
+where
+    D: digest::Update + digest::BlockInput + digest::FixedOutput + digest::Reset + Default + Clone + 'static,
+{
+    // hmac accepts any length of key, so this never fails
+    let mac = hmac::Hmac::<D>::new_varkey(key).expect("hmac must accept any key length");
+    Box::new(HmacHasher(mac))
+}
+
+/// None means the algorithm is read from manifest, the key must be given by merge_configured
+#[derive(Clone, PartialEq, Eq)]
+pub struct HmacKey(Option<Vec<u8>>);
+
+impl std::fmt::Debug for HmacKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // never show the key
+        write!(f, "HmacKey(..)")
+    }
+}
+
+/// input is buffered up to this size before multithreaded blake3 update,
+/// because smaller input can't be split into enough chunks
+const BLAKE3_MULTITHREAD_BUFFER_SIZE: usize = 1024 * 1024;
+
+struct Blake3Hasher {
+    h: blake3::Hasher,
+    outputsize: usize,
+    multithread: bool,
+    buf: Vec<u8>,
+}
+
+impl Blake3Hasher {
+    fn flush(&mut self) {
+        if !self.buf.is_empty() {
+            self.h.update_rayon(&self.buf);
+            self.buf.clear();
+        }
+    }
+}
+
+impl Hasher for Blake3Hasher {
+    fn update(&mut self, mut data: &[u8]) {
+        if !self.multithread {
+            self.h.update(data);
+            return;
+        }
+        if !self.buf.is_empty() {
+            let n = std::cmp::min(BLAKE3_MULTITHREAD_BUFFER_SIZE - self.buf.len(), data.len());
+            self.buf.extend_from_slice(&data[..n]);
+            data = &data[n..];
+            if self.buf.len() < BLAKE3_MULTITHREAD_BUFFER_SIZE {
+                return;
+            }
+            self.flush();
+        }
+        // large input(e.g. mmap) is hashed in place, not copied to the buffer
+        if data.len() >= BLAKE3_MULTITHREAD_BUFFER_SIZE {
+            self.h.update_rayon(data);
+        } else {
+            self.buf.extend_from_slice(data);
+        }
+    }
+    fn finalize_reset(&mut self) -> Vec<u8> {
+        self.flush();
+        let mut ret = vec![0u8; self.outputsize];
+        self.h.finalize_xof().fill(&mut ret);
+        self.h.reset();
+        ret
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum Blake3Mode {
+    Hash,
+    /// keyed hash with 32 bytes key
+    Keyed([u8; 32]),
+    /// key derivation with context string
+    DeriveKey(String),
+}
+
+impl std::fmt::Debug for Blake3Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Blake3Mode::Hash => write!(f, "Hash"),
+            // never show the key
+            Blake3Mode::Keyed(_) => write!(f, "Keyed(..)"),
+            Blake3Mode::DeriveKey(context) => write!(f, "DeriveKey({:?})", context),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blake3Params {
+    pub outputlength: usize,
+    pub mode: Blake3Mode,
+    pub multithread: bool,
+}
+
+impl Blake3Params {
+    pub fn new(outputlength: usize) -> Blake3Params {
+        Blake3Params {
+            outputlength,
+            mode: Blake3Mode::Hash,
+            multithread: false,
+        }
+    }
+    pub fn validate(&self) -> Result<(), ApplicationError> {
+        if self.outputlength == 0 {
+            return Err(ApplicationError::from_parameter(
+                "outputlength",
+                "output length must be 1 or more bytes(0)",
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Blake2Variant {
+    B,
+    S,
+    /// 4-way parallel BLAKE2b
+    Bp,
+    /// 8-way parallel BLAKE2s
+    Sp,
+}
+
+impl Blake2Variant {
+    pub fn from_name(name: &str) -> Result<Blake2Variant, ApplicationError> {
+        match name {
+            "b" => Ok(Blake2Variant::B),
+            "s" => Ok(Blake2Variant::S),
+            "bp" => Ok(Blake2Variant::Bp),
+            "sp" => Ok(Blake2Variant::Sp),
+            _ => Err(ApplicationError::from_parameter(
+                "algorithm",
+                format!("Unknown algorithm({})", name).as_str(),
+            )),
+        }
+    }
+    /// max length of output and key
+    fn max_length(self) -> usize {
+        match self {
+            Blake2Variant::B | Blake2Variant::Bp => blake2b_simd::OUTBYTES,
+            Blake2Variant::S | Blake2Variant::Sp => blake2s_simd::OUTBYTES,
+        }
+    }
+    /// length of salt and personalization, parallel variants don't support them
+    fn salt_length(self) -> usize {
+        match self {
+            Blake2Variant::B => blake2b_simd::SALTBYTES,
+            Blake2Variant::S => blake2s_simd::SALTBYTES,
+            Blake2Variant::Bp | Blake2Variant::Sp => 0,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Blake2Params {
+    pub variant: Blake2Variant,
+    pub outputlength: usize,
+    pub key: Vec<u8>,
+    pub salt: Vec<u8>,
+    pub personal: Vec<u8>,
+}
+
+impl std::fmt::Debug for Blake2Params {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // never show the key
+        f.debug_struct("Blake2Params")
+            .field("variant", &self.variant)
+            .field("outputlength", &self.outputlength)
+            .field("keyed", &!self.key.is_empty())
+            .field("salt", &self.salt)
+            .field("personal", &self.personal)
+            .finish()
+    }
+}
+
+impl Blake2Params {
+    /// default parameters, output length is max length of the variant
+    pub fn new(variant: Blake2Variant) -> Blake2Params {
+        Blake2Params {
+            variant,
+            outputlength: variant.max_length(),
+            key: Vec::new(),
+            salt: Vec::new(),
+            personal: Vec::new(),
+        }
+    }
+    /// plain BLAKE2b or BLAKE2s without any parameters
+    fn is_plain(&self) -> bool {
+        (self.variant == Blake2Variant::B || self.variant == Blake2Variant::S)
+            && self.outputlength == self.variant.max_length()
+            && self.key.is_empty()
+            && self.salt.is_empty()
+            && self.personal.is_empty()
+    }
+    pub fn validate(&self) -> Result<(), ApplicationError> {
+        let max = self.variant.max_length();
+        if self.outputlength == 0 || self.outputlength > max {
+            return Err(ApplicationError::from_parameter(
+                "outputlength",
+                format!("output length must be 1 to {} bytes({})", max, self.outputlength).as_str(),
+            ));
+        }
+        if self.key.len() > max {
+            return Err(ApplicationError::from_parameter(
+                "key-file",
+                format!("key must be up to {} bytes, but {} bytes", max, self.key.len()).as_str(),
+            ));
+        }
+        let saltmax = self.variant.salt_length();
+        for (name, v) in &[("salt", &self.salt), ("personal", &self.personal)] {
+            if saltmax == 0 && !v.is_empty() {
+                return Err(ApplicationError::from_parameter(
+                    name,
+                    format!("{} is not supported by parallel variants", name).as_str(),
+                ));
+            }
+            if v.len() > saltmax {
+                return Err(ApplicationError::from_parameter(
+                    name,
+                    format!("{} must be up to {} bytes, but {} bytes", name, saltmax, v.len()).as_str(),
+                ));
+            }
+        }
+        Ok(())
+    }
+    fn create_state(&self) -> Blake2State {
+        match self.variant {
+            Blake2Variant::B => Blake2State::B(
+                blake2b_simd::Params::new()
+                    .hash_length(self.outputlength)
+                    .key(&self.key)
+                    .salt(&self.salt)
+                    .personal(&self.personal)
+                    .to_state(),
+            ),
+            Blake2Variant::S => Blake2State::S(
+                blake2s_simd::Params::new()
+                    .hash_length(self.outputlength)
+                    .key(&self.key)
+                    .salt(&self.salt)
+                    .personal(&self.personal)
+                    .to_state(),
+            ),
+            Blake2Variant::Bp => Blake2State::Bp(
+                blake2b_simd::blake2bp::Params::new()
+                    .hash_length(self.outputlength)
+                    .key(&self.key)
+                    .to_state(),
+            ),
+            Blake2Variant::Sp => Blake2State::Sp(
+                blake2s_simd::blake2sp::Params::new()
+                    .hash_length(self.outputlength)
+                    .key(&self.key)
+                    .to_state(),
+            ),
+        }
+    }
+}
+
+enum Blake2State {
+    B(blake2b_simd::State),
+    S(blake2s_simd::State),
+    Bp(blake2b_simd::blake2bp::State),
+    Sp(blake2s_simd::blake2sp::State),
+}
+
+struct Blake2Hasher {
+    params: Blake2Params,
+    state: Blake2State,
+}
+
+impl Hasher for Blake2Hasher {
+    fn update(&mut self, data: &[u8]) {
+        match &mut self.state {
+            Blake2State::B(h) => {
+                h.update(data);
+            }
+            Blake2State::S(h) => {
+                h.update(data);
+            }
+            Blake2State::Bp(h) => {
+                h.update(data);
+            }
+            Blake2State::Sp(h) => {
+                h.update(data);
+            }
+        }
+    }
+    fn finalize_reset(&mut self) -> Vec<u8> {
+        let ret = match &self.state {
+            Blake2State::B(h) => h.finalize().as_bytes().to_vec(),
+            Blake2State::S(h) => h.finalize().as_bytes().to_vec(),
+            Blake2State::Bp(h) => h.finalize().as_bytes().to_vec(),
+            Blake2State::Sp(h) => h.finalize().as_bytes().to_vec(),
+        };
+        self.state = self.params.create_state();
+        ret
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Algorithm {
+    Md5,
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+    Sha512Trunc224,
+    Sha512Trunc256,
+    Sha3_224,
+    Sha3_256,
+    Sha3_384,
+    Sha3_512,
+    Shake128(usize),
+    Shake256(usize),
+    Blake2(Blake2Params),
+    Blake3(Blake3Params),
+    /// CRC-32/ISO-HDLC, used by zlib
+    Crc32,
+    /// CRC-32/ISCSI(Castagnoli)
+    Crc32c,
+    /// CRC-64/XZ
+    Crc64,
+    Adler32,
+    /// xxhash algorithms with seed
+    Xxh32(u32),
+    Xxh64(u64),
+    Xxh3_64(u64),
+    Xxh3_128(u64),
+    /// HMAC with fixed output hash algorithm
+    Hmac(Box<Algorithm>, HmacKey),
+}
+
+pub const ALGORITHM_NAMES: &[&str] = &[
+    "md5",
+    "sha1",
+    "sha2-224",
+    "sha2-256",
+    "sha2-384",
+    "sha2-512",
+    "sha2-512/224",
+    "sha2-512/256",
+    "sha3-224",
+    "sha3-256",
+    "sha3-384",
+    "sha3-512",
+    "shake128",
+    "shake256",
+    "blake2b",
+    "blake2s",
+    "blake2bp",
+    "blake2sp",
+    "blake3",
+    "crc32",
+    "crc32c",
+    "crc64",
+    "adler32",
+    "xxh32",
+    "xxh64",
+    "xxh3-64",
+    "xxh3-128",
+];
+
+/// digest length in bytes of algorithms which can be detected by `check auto`.
+/// non-cryptographic checksums are excluded, because they share length with each other and MD5
+const DETECTABLE_LENGTHS: &[(&str, usize)] = &[
+    ("md5", 16),
+    ("sha1", 20),
+    ("sha2-224", 28),
+    ("sha2-256", 32),
+    ("sha2-384", 48),
+    ("sha2-512", 64),
+    ("sha2-512/224", 28),
+    ("sha2-512/256", 32),
+    ("sha3-224", 28),
+    ("sha3-256", 32),
+    ("sha3-384", 48),
+    ("sha3-512", 64),
+    ("blake2b", 64),
+    ("blake2s", 32),
+    ("blake3", 32),
+];
+
+/// algorithm names which output digest of the length, more than one name means ambiguous
+pub fn names_from_digest_length(length: usize) -> Vec<&'static str> {
+    DETECTABLE_LENGTHS
+        .iter()
+        .filter(|(_, l)| *l == length)
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+impl Algorithm {
+    /// parse algorithm name(one of ALGORITHM_NAMES), outputlength is used only by shake.
+    /// blake3 uses default output length(32 bytes)
+    pub fn from_name(name: &str, outputlength: usize) -> Result<Algorithm, ApplicationError> {
+        match name.to_lowercase().as_str() {
+            "md5" => Ok(Algorithm::Md5),
+            "sha1" => Ok(Algorithm::Sha1),
+            "sha2-224" => Ok(Algorithm::Sha224),
+            "sha2-256" => Ok(Algorithm::Sha256),
+            "sha2-384" => Ok(Algorithm::Sha384),
+            "sha2-512" => Ok(Algorithm::Sha512),
+            "sha2-512/224" => Ok(Algorithm::Sha512Trunc224),
+            "sha2-512/256" => Ok(Algorithm::Sha512Trunc256),
+            "sha3-224" => Ok(Algorithm::Sha3_224),
+            "sha3-256" => Ok(Algorithm::Sha3_256),
+            "sha3-384" => Ok(Algorithm::Sha3_384),
+            "sha3-512" => Ok(Algorithm::Sha3_512),
+            "shake128" => Ok(Algorithm::Shake128(outputlength)),
+            "shake256" => Ok(Algorithm::Shake256(outputlength)),
+            "blake2b" => Ok(Algorithm::Blake2(Blake2Params::new(Blake2Variant::B))),
+            "blake2s" => Ok(Algorithm::Blake2(Blake2Params::new(Blake2Variant::S))),
+            "blake2bp" => Ok(Algorithm::Blake2(Blake2Params::new(Blake2Variant::Bp))),
+            "blake2sp" => Ok(Algorithm::Blake2(Blake2Params::new(Blake2Variant::Sp))),
+            "blake3" => Ok(Algorithm::Blake3(Blake3Params::new(blake3::OUT_LEN))),
+            "crc32" => Ok(Algorithm::Crc32),
+            "crc32c" => Ok(Algorithm::Crc32c),
+            "crc64" => Ok(Algorithm::Crc64),
+            "adler32" => Ok(Algorithm::Adler32),
+            "xxh32" => Ok(Algorithm::Xxh32(0)),
+            "xxh64" => Ok(Algorithm::Xxh64(0)),
+            "xxh3-64" => Ok(Algorithm::Xxh3_64(0)),
+            "xxh3-128" => Ok(Algorithm::Xxh3_128(0)),
+            _ => Err(ApplicationError::from_parameter(
+                "algorithm",
+                format!("Unknown algorithm({})", name).as_str(),
+            )),
+        }
+    }
+    pub fn from_sha2_length(bitlength: &str) -> Result<Algorithm, ApplicationError> {
+        match bitlength {
+            "224" => Ok(Algorithm::Sha224),
+            "256" => Ok(Algorithm::Sha256),
+            "384" => Ok(Algorithm::Sha384),
+            "512" => Ok(Algorithm::Sha512),
+            "512/224" => Ok(Algorithm::Sha512Trunc224),
+            "512/256" => Ok(Algorithm::Sha512Trunc256),
+            _ => Err(ApplicationError::from_parameter(
+                "length",
+                format!("invalid length parameter({})", bitlength).as_str(),
+            )),
+        }
+    }
+    pub fn from_sha3_length(bitlength: &str) -> Result<Algorithm, ApplicationError> {
+        match bitlength {
+            "224" => Ok(Algorithm::Sha3_224),
+            "256" => Ok(Algorithm::Sha3_256),
+            "384" => Ok(Algorithm::Sha3_384),
+            "512" => Ok(Algorithm::Sha3_512),
+            _ => Err(ApplicationError::from_parameter(
+                "length",
+                format!("invalid length parameter({})", bitlength).as_str(),
+            )),
+        }
+    }
+    pub fn from_shake_length(bitlength: &str, outlength: usize) -> Result<Algorithm, ApplicationError> {
+        match bitlength {
+            "128" => Ok(Algorithm::Shake128(outlength)),
+            "256" => Ok(Algorithm::Shake256(outlength)),
+            _ => Err(ApplicationError::from_parameter(
+                "length",
+                format!("invalid length parameter({})", bitlength).as_str(),
+            )),
+        }
+    }
+    /// name in ALGORITHM_NAMES, `hmac-` is prefixed for HMAC
+    pub fn name(&self) -> String {
+        let name = match self {
+            Algorithm::Hmac(inner, _) => return format!("hmac-{}", inner.name()),
+            Algorithm::Md5 => "md5",
+            Algorithm::Sha1 => "sha1",
+            Algorithm::Sha224 => "sha2-224",
+            Algorithm::Sha256 => "sha2-256",
+            Algorithm::Sha384 => "sha2-384",
+            Algorithm::Sha512 => "sha2-512",
+            Algorithm::Sha512Trunc224 => "sha2-512/224",
+            Algorithm::Sha512Trunc256 => "sha2-512/256",
+            Algorithm::Sha3_224 => "sha3-224",
+            Algorithm::Sha3_256 => "sha3-256",
+            Algorithm::Sha3_384 => "sha3-384",
+            Algorithm::Sha3_512 => "sha3-512",
+            Algorithm::Shake128(_) => "shake128",
+            Algorithm::Shake256(_) => "shake256",
+            Algorithm::Blake2(params) => match params.variant {
+                Blake2Variant::B => "blake2b",
+                Blake2Variant::S => "blake2s",
+                Blake2Variant::Bp => "blake2bp",
+                Blake2Variant::Sp => "blake2sp",
+            },
+            Algorithm::Blake3(_) => "blake3",
+            Algorithm::Crc32 => "crc32",
+            Algorithm::Crc32c => "crc32c",
+            Algorithm::Crc64 => "crc64",
+            Algorithm::Adler32 => "adler32",
+            Algorithm::Xxh32(_) => "xxh32",
+            Algorithm::Xxh64(_) => "xxh64",
+            Algorithm::Xxh3_64(_) => "xxh3-64",
+            Algorithm::Xxh3_128(_) => "xxh3-128",
+        };
+        name.to_owned()
+    }
+    /// name and parameters like `shake128;outputlength=64`, used in manifest header.
+    /// keys are never included
+    pub fn to_spec(&self) -> String {
+        let mut params = Vec::new();
+        match self {
+            Algorithm::Hmac(inner, _) => return format!("hmac-{}", inner.to_spec()),
+            Algorithm::Shake128(outputlength) | Algorithm::Shake256(outputlength) => {
+                params.push(format!("outputlength={}", outputlength));
+            }
+            Algorithm::Blake2(p) => {
+                if p.outputlength != p.variant.max_length() {
+                    params.push(format!("outputlength={}", p.outputlength));
+                }
+                if !p.salt.is_empty() {
+                    params.push(format!("salt={}", digestutil::to_hex_string(&p.salt)));
+                }
+                if !p.personal.is_empty() {
+                    params.push(format!("personal={}", digestutil::to_hex_string(&p.personal)));
+                }
+            }
+            Algorithm::Blake3(p) => {
+                if p.outputlength != blake3::OUT_LEN {
+                    params.push(format!("outputlength={}", p.outputlength));
+                }
+                if let Blake3Mode::DeriveKey(context) = &p.mode {
+                    params.push(format!("derive-key={}", digestutil::to_hex_string(context.as_bytes())));
+                }
+            }
+            Algorithm::Xxh32(seed) if *seed != 0 => params.push(format!("seed={}", seed)),
+            Algorithm::Xxh64(seed) | Algorithm::Xxh3_64(seed) | Algorithm::Xxh3_128(seed) if *seed != 0 => {
+                params.push(format!("seed={}", seed))
+            }
+            _ => {}
+        }
+        params.insert(0, self.name());
+        params.join(";")
+    }
+    /// parse the string created by to_spec
+    pub fn from_spec(spec: &str) -> Option<Algorithm> {
+        if let Some(inner) = spec.strip_prefix("hmac-") {
+            // key is never written in manifest, it must be given by command line
+            return Algorithm::from_spec(inner)?.into_hmac(None).ok();
+        }
+        let mut parts = spec.split(';');
+        let mut algorithm = Algorithm::from_name(parts.next()?, 0).ok()?;
+        for part in parts {
+            let (key, value) = part.split_once('=')?;
+            match (&mut algorithm, key) {
+                (Algorithm::Shake128(l) | Algorithm::Shake256(l), "outputlength") => *l = value.parse().ok()?,
+                (Algorithm::Blake2(p), "outputlength") => p.outputlength = value.parse().ok()?,
+                (Algorithm::Blake2(p), "salt") => p.salt = digestutil::from_hex_string(value)?,
+                (Algorithm::Blake2(p), "personal") => p.personal = digestutil::from_hex_string(value)?,
+                (Algorithm::Blake3(p), "outputlength") => p.outputlength = value.parse().ok()?,
+                (Algorithm::Blake3(p), "derive-key") => {
+                    let context = String::from_utf8(digestutil::from_hex_string(value)?).ok()?;
+                    p.mode = Blake3Mode::DeriveKey(context);
+                }
+                (Algorithm::Xxh32(seed), "seed") => *seed = value.parse().ok()?,
+                (Algorithm::Xxh64(seed) | Algorithm::Xxh3_64(seed) | Algorithm::Xxh3_128(seed), "seed") => {
+                    *seed = value.parse().ok()?
+                }
+                _ => return None,
+            }
+        }
+        match &algorithm {
+            Algorithm::Blake2(p) => p.validate().ok()?,
+            Algorithm::Blake3(p) => p.validate().ok()?,
+            _ => {}
+        }
+        Some(algorithm)
+    }
+    /// tag name used in BSD style checksum line(`TAG (file) = hash`)
+    pub fn bsd_tag(&self) -> String {
+        let tag = match self {
+            Algorithm::Hmac(inner, _) => return format!("HMAC-{}", inner.bsd_tag()),
+            Algorithm::Md5 => "MD5",
+            Algorithm::Sha1 => "SHA1",
+            Algorithm::Sha224 => "SHA224",
+            Algorithm::Sha256 => "SHA256",
+            Algorithm::Sha384 => "SHA384",
+            Algorithm::Sha512 => "SHA512",
+            Algorithm::Sha512Trunc224 => "SHA512t224",
+            Algorithm::Sha512Trunc256 => "SHA512t256",
+            Algorithm::Sha3_224 => "SHA3-224",
+            Algorithm::Sha3_256 => "SHA3-256",
+            Algorithm::Sha3_384 => "SHA3-384",
+            Algorithm::Sha3_512 => "SHA3-512",
+            Algorithm::Shake128(_) => "SHAKE128",
+            Algorithm::Shake256(_) => "SHAKE256",
+            Algorithm::Blake2(params) => {
+                let name = match params.variant {
+                    Blake2Variant::B => "BLAKE2b",
+                    Blake2Variant::S => "BLAKE2s",
+                    Blake2Variant::Bp => "BLAKE2bp",
+                    Blake2Variant::Sp => "BLAKE2sp",
+                };
+                // same as b2sum, output length is added only if it is not default
+                if params.outputlength == params.variant.max_length() {
+                    return name.to_owned();
+                }
+                return format!("{}-{}", name, params.outputlength * 8);
+            }
+            Algorithm::Blake3(_) => "BLAKE3",
+            // same tag as `cksum -a crc32b --tag`
+            Algorithm::Crc32 => "CRC32B",
+            Algorithm::Crc32c => "CRC32C",
+            Algorithm::Crc64 => "CRC64",
+            Algorithm::Adler32 => "ADLER32",
+            Algorithm::Xxh32(_) => "XXH32",
+            Algorithm::Xxh64(_) => "XXH64",
+            Algorithm::Xxh3_64(_) => "XXH3",
+            Algorithm::Xxh3_128(_) => "XXH128",
+        };
+        tag.to_owned()
+    }
+    /// parse BLAKE2 tag like `BLAKE2b` or `BLAKE2b-256`(bit length), tag must be uppercase
+    fn from_blake2_tag(tag: &str, outputlength: usize) -> Option<Algorithm> {
+        let (variant, rest) = [
+            ("BLAKE2BP", Blake2Variant::Bp),
+            ("BLAKE2SP", Blake2Variant::Sp),
+            ("BLAKE2B", Blake2Variant::B),
+            ("BLAKE2S", Blake2Variant::S),
+        ]
+        .iter()
+        .find_map(|(prefix, variant)| tag.strip_prefix(prefix).map(|rest| (*variant, rest)))?;
+        if let Some(bits) = rest.strip_prefix('-') {
+            if bits.parse::<usize>().ok()? != outputlength * 8 {
+                return None;
+            }
+        } else if !rest.is_empty() {
+            return None;
+        }
+        let mut params = Blake2Params::new(variant);
+        params.outputlength = outputlength;
+        params.validate().ok()?;
+        Some(Algorithm::Blake2(params))
+    }
+    /// parse BSD style tag, outputlength is used only by shake, blake2 and blake3
+    pub fn from_bsd_tag(tag: &str, outputlength: usize) -> Option<Algorithm> {
+        let tag = tag.to_uppercase();
+        if tag.starts_with("BLAKE2") {
+            return Algorithm::from_blake2_tag(&tag, outputlength);
+        }
+        if let Some(inner) = tag.strip_prefix("HMAC-") {
+            // key is never written in manifest, it must be given by command line
+            return Algorithm::from_bsd_tag(inner, outputlength)?.into_hmac(None).ok();
+        }
+        match tag.as_str() {
+            "MD5" => Some(Algorithm::Md5),
+            "SHA1" => Some(Algorithm::Sha1),
+            "SHA224" => Some(Algorithm::Sha224),
+            "SHA256" => Some(Algorithm::Sha256),
+            "SHA384" => Some(Algorithm::Sha384),
+            "SHA512" => Some(Algorithm::Sha512),
+            "SHA512T224" | "SHA512/224" => Some(Algorithm::Sha512Trunc224),
+            "SHA512T256" | "SHA512/256" => Some(Algorithm::Sha512Trunc256),
+            "SHA3-224" => Some(Algorithm::Sha3_224),
+            "SHA3-256" => Some(Algorithm::Sha3_256),
+            "SHA3-384" => Some(Algorithm::Sha3_384),
+            "SHA3-512" => Some(Algorithm::Sha3_512),
+            "SHAKE128" => Some(Algorithm::Shake128(outputlength)),
+            "SHAKE256" => Some(Algorithm::Shake256(outputlength)),
+            "BLAKE3" => Some(Algorithm::Blake3(Blake3Params::new(outputlength))),
+            "CRC32B" | "CRC32" => Some(Algorithm::Crc32),
+            "CRC32C" => Some(Algorithm::Crc32c),
+            "CRC64" => Some(Algorithm::Crc64),
+            "ADLER32" => Some(Algorithm::Adler32),
+            "XXH32" => Some(Algorithm::Xxh32(0)),
+            "XXH64" => Some(Algorithm::Xxh64(0)),
+            "XXH3" | "XXH3_64BITS" => Some(Algorithm::Xxh3_64(0)),
+            "XXH128" => Some(Algorithm::Xxh3_128(0)),
+            _ => None,
+        }
+    }
+    pub fn from_crc_variant(algorithm: &str) -> Result<Algorithm, ApplicationError> {
+        match algorithm {
+            "crc32" => Ok(Algorithm::Crc32),
+            "crc32c" => Ok(Algorithm::Crc32c),
+            "crc64" => Ok(Algorithm::Crc64),
+            _ => Err(ApplicationError::from_parameter(
+                "algorithm",
+                format!("Unknown algorithm({})", algorithm).as_str(),
+            )),
+        }
+    }
+    pub fn from_xxhash_length(length: &str, seed: u64) -> Result<Algorithm, ApplicationError> {
+        match length {
+            "32" => {
+                if seed > u32::MAX as u64 {
+                    return Err(ApplicationError::from_parameter(
+                        "seed",
+                        format!("seed of xxh32 must be 32bit value({})", seed).as_str(),
+                    ));
+                }
+                Ok(Algorithm::Xxh32(seed as u32))
+            }
+            "64" => Ok(Algorithm::Xxh64(seed)),
+            "3-64" => Ok(Algorithm::Xxh3_64(seed)),
+            "3-128" => Ok(Algorithm::Xxh3_128(seed)),
+            _ => Err(ApplicationError::from_parameter(
+                "length",
+                format!("invalid length parameter({})", length).as_str(),
+            )),
+        }
+    }
+    /// wrap the algorithm with HMAC, only fixed output cryptographic hash can be used
+    pub fn with_hmac(self, key: Vec<u8>) -> Result<Algorithm, ApplicationError> {
+        self.into_hmac(Some(key))
+    }
+    fn into_hmac(self, key: Option<Vec<u8>>) -> Result<Algorithm, ApplicationError> {
+        match self {
+            Algorithm::Md5
+            | Algorithm::Sha1
+            | Algorithm::Sha224
+            | Algorithm::Sha256
+            | Algorithm::Sha384
+            | Algorithm::Sha512
+            | Algorithm::Sha512Trunc224
+            | Algorithm::Sha512Trunc256
+            | Algorithm::Sha3_224
+            | Algorithm::Sha3_256
+            | Algorithm::Sha3_384
+            | Algorithm::Sha3_512 => Ok(Algorithm::Hmac(Box::new(self), HmacKey(key))),
+            Algorithm::Blake2(ref params) if params.is_plain() => Ok(Algorithm::Hmac(Box::new(self), HmacKey(key))),
+            _ => Err(ApplicationError::from_parameter(
+                "hmac",
+                format!("hmac is not supported by {}", self.bsd_tag()).as_str(),
+            )),
+        }
+    }
+    /// true if the algorithm has a secret key(HMAC, keyed BLAKE2 and BLAKE3)
+    pub fn is_keyed(&self) -> bool {
+        match self {
+            Algorithm::Hmac(_, key) => key.0.is_some(),
+            Algorithm::Blake2(p) => !p.key.is_empty(),
+            Algorithm::Blake3(p) => matches!(p.mode, Blake3Mode::Keyed(_)),
+            _ => false,
+        }
+    }
+    /// true if the algorithm is HMAC read from manifest, and the key is not given yet
+    pub fn requires_key(&self) -> bool {
+        matches!(self, Algorithm::Hmac(_, HmacKey(None)))
+    }
+    /// merge algorithm read from manifest line with the one configured by command line,
+    /// because keys and other parameters are never written in manifest.
+    /// if configured algorithm is keyed, the line must be the same algorithm,
+    /// otherwise the entry would be checked without the key
+    pub fn merge_configured(&self, configured: &Algorithm) -> Result<Algorithm, ApplicationError> {
+        let merged = match (self, configured) {
+            (Algorithm::Blake3(line), Algorithm::Blake3(conf)) => Algorithm::Blake3(Blake3Params {
+                outputlength: line.outputlength,
+                ..conf.clone()
+            }),
+            (Algorithm::Blake2(line), Algorithm::Blake2(conf)) if line.variant == conf.variant => {
+                Algorithm::Blake2(Blake2Params {
+                    outputlength: line.outputlength,
+                    ..conf.clone()
+                })
+            }
+            (Algorithm::Xxh32(_), Algorithm::Xxh32(_))
+            | (Algorithm::Xxh64(_), Algorithm::Xxh64(_))
+            | (Algorithm::Xxh3_64(_), Algorithm::Xxh3_64(_))
+            | (Algorithm::Xxh3_128(_), Algorithm::Xxh3_128(_)) => configured.clone(),
+            (Algorithm::Hmac(line, _), Algorithm::Hmac(conf, key)) if line == conf => {
+                Algorithm::Hmac(line.clone(), key.clone())
+            }
+            _ => self.clone(),
+        };
+        if configured.is_keyed() && !merged.is_keyed() {
+            return Err(ApplicationError::from_parameter(
+                "algorithm",
+                format!(
+                    "{} in manifest cannot be checked with the key of {}",
+                    self.name(),
+                    configured.name()
+                )
+                .as_str(),
+            ));
+        }
+        Ok(merged)
+    }
+    /// fails if requires_key is true(HMAC read from manifest must be merged with the keyed algorithm first),
+    /// or parameters of BLAKE2/BLAKE3 are invalid
+    pub fn create_hasher(&self) -> Result<Box<dyn Hasher>, ApplicationError> {
+        let hasher: Box<dyn Hasher> = match self {
+            Algorithm::Md5 => Box::new(FixedOutputHasher(md5::Md5::new())),
+            Algorithm::Sha1 => Box::new(FixedOutputHasher(sha1::Sha1::new())),
+            Algorithm::Sha224 => Box::new(FixedOutputHasher(sha2::Sha224::new())),
+            Algorithm::Sha256 => Box::new(FixedOutputHasher(sha2::Sha256::new())),
+            Algorithm::Sha384 => Box::new(FixedOutputHasher(sha2::Sha384::new())),
+            Algorithm::Sha512 => Box::new(FixedOutputHasher(sha2::Sha512::new())),
+            Algorithm::Sha512Trunc224 => Box::new(FixedOutputHasher(sha2::Sha512Trunc224::new())),
+            Algorithm::Sha512Trunc256 => Box::new(FixedOutputHasher(sha2::Sha512Trunc256::new())),
+            Algorithm::Sha3_224 => Box::new(FixedOutputHasher(sha3::Sha3_224::new())),
+            Algorithm::Sha3_256 => Box::new(FixedOutputHasher(sha3::Sha3_256::new())),
+            Algorithm::Sha3_384 => Box::new(FixedOutputHasher(sha3::Sha3_384::new())),
+            Algorithm::Sha3_512 => Box::new(FixedOutputHasher(sha3::Sha3_512::new())),
+            Algorithm::Shake128(outputsize) => Box::new(ExtendableOutputHasher {
+                d: sha3::Shake128::default(),
+                outputsize: *outputsize,
+            }),
+            Algorithm::Shake256(outputsize) => Box::new(ExtendableOutputHasher {
+                d: sha3::Shake256::default(),
+                outputsize: *outputsize,
+            }),
+            Algorithm::Blake2(params) => {
+                // fields are public, so that invalid length may reach here and blake2 panics with it
+                params.validate()?;
+                Box::new(Blake2Hasher {
+                    params: params.clone(),
+                    state: params.create_state(),
+                })
+            }
+            Algorithm::Blake3(params) => {
+                params.validate()?;
+                let h = match &params.mode {
+                    Blake3Mode::Hash => blake3::Hasher::new(),
+                    Blake3Mode::Keyed(key) => blake3::Hasher::new_keyed(key),
+                    Blake3Mode::DeriveKey(context) => blake3::Hasher::new_derive_key(context),
+                };
+                Box::new(Blake3Hasher {
+                    h,
+                    outputsize: params.outputlength,
+                    multithread: params.multithread,
+                    buf: Vec::new(),
+                })
+            }
+            Algorithm::Crc32 => Box::new(Crc32Hasher {
+                crc: &CRC32,
+                d: CRC32.digest(),
+            }),
+            Algorithm::Crc32c => Box::new(Crc32Hasher {
+                crc: &CRC32C,
+                d: CRC32C.digest(),
+            }),
+            Algorithm::Crc64 => Box::new(Crc64Hasher {
+                crc: &CRC64,
+                d: CRC64.digest(),
+            }),
+            Algorithm::Adler32 => Box::new(Adler32Hasher(adler::Adler32::new())),
+            Algorithm::Xxh32(seed) => Box::new(Xxh32Hasher {
+                h: xxhash_rust::xxh32::Xxh32::new(*seed),
+                seed: *seed,
+            }),
+            Algorithm::Xxh64(seed) => Box::new(Xxh64Hasher {
+                h: xxhash_rust::xxh64::Xxh64::new(*seed),
+                seed: *seed,
+            }),
+            Algorithm::Xxh3_64(seed) => Box::new(Xxh3Hasher {
+                h: xxhash_rust::xxh3::Xxh3::with_seed(*seed),
+                is128: false,
+            }),
+            Algorithm::Xxh3_128(seed) => Box::new(Xxh3Hasher {
+                h: xxhash_rust::xxh3::Xxh3::with_seed(*seed),
+                is128: true,
+            }),
+            Algorithm::Hmac(inner, key) => {
+                let key = match key.0.as_deref() {
+                    Some(v) => v,
+                    None => {
+                        return Err(ApplicationError::from_parameter(
+                            "key",
+                            format!("key required for {}", self.name()).as_str(),
+                        ))
+                    }
+                };
+                match **inner {
+                    Algorithm::Md5 => create_hmac_hasher::<md5::Md5>(key),
+                    Algorithm::Sha1 => create_hmac_hasher::<sha1::Sha1>(key),
+                    Algorithm::Sha224 => create_hmac_hasher::<sha2::Sha224>(key),
+                    Algorithm::Sha256 => create_hmac_hasher::<sha2::Sha256>(key),
+                    Algorithm::Sha384 => create_hmac_hasher::<sha2::Sha384>(key),
+                    Algorithm::Sha512 => create_hmac_hasher::<sha2::Sha512>(key),
+                    Algorithm::Sha512Trunc224 => create_hmac_hasher::<sha2::Sha512Trunc224>(key),
+                    Algorithm::Sha512Trunc256 => create_hmac_hasher::<sha2::Sha512Trunc256>(key),
+                    Algorithm::Sha3_224 => create_hmac_hasher::<sha3::Sha3_224>(key),
+                    Algorithm::Sha3_256 => create_hmac_hasher::<sha3::Sha3_256>(key),
+                    Algorithm::Sha3_384 => create_hmac_hasher::<sha3::Sha3_384>(key),
+                    Algorithm::Sha3_512 => create_hmac_hasher::<sha3::Sha3_512>(key),
+                    Algorithm::Blake2(ref params) if params.variant == Blake2Variant::B => {
+                        create_hmac_hasher::<blake2::Blake2b>(key)
+                    }
+                    Algorithm::Blake2(ref params) if params.variant == Blake2Variant::S => {
+                        create_hmac_hasher::<blake2::Blake2s>(key)
+                    }
+                    // with_hmac accepts only above algorithms
+                    _ => unreachable!("hmac is not supported by {}", inner.bsd_tag()),
+                }
+            }
+        };
+        Ok(hasher)
+    }
+}
+
+/// feeds same data into several hashers, so that input is read only once
+pub struct MultiHasher {
+    hashers: Vec<Box<dyn Hasher>>,
+}
+
+impl MultiHasher {
+    pub fn new(algorithms: &[Algorithm]) -> Result<MultiHasher, ApplicationError> {
+        Ok(MultiHasher {
+            hashers: algorithms
+                .iter()
+                .map(|a| a.create_hasher())
+                .collect::<Result<Vec<Box<dyn Hasher>>, ApplicationError>>()?,
+        })
+    }
+    pub fn finalize_reset(&mut self) -> Vec<Vec<u8>> {
+        self.hashers.iter_mut().map(|h| h.finalize_reset()).collect()
+    }
+}
+
+impl digest::Update for MultiHasher {
+    fn update(&mut self, data: impl AsRef<[u8]>) {
+        let data = data.as_ref();
+        for h in self.hashers.iter_mut() {
+            h.update(data);
+        }
+    }
+}
+
+impl digest::Update for Box<dyn Hasher> {
+    fn update(&mut self, data: impl AsRef<[u8]>) {
+        Hasher::update(self.as_mut(), data.as_ref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hmac_sha256() -> Algorithm {
+        Algorithm::Sha256.with_hmac(b"secret".to_vec()).unwrap()
+    }
+
+    #[test]
+    fn keyed_algorithm_is_not_replaced_by_unkeyed_line() {
+        // manifest created without key must not be checked without key
+        assert!(Algorithm::Sha256.merge_configured(&hmac_sha256()).is_err());
+        assert!(Algorithm::Md5.merge_configured(&hmac_sha256()).is_err());
+        let mut blake2 = Blake2Params::new(Blake2Variant::B);
+        blake2.key = b"secret".to_vec();
+        assert!(Algorithm::Sha256.merge_configured(&Algorithm::Blake2(blake2)).is_err());
+        let mut blake3 = Blake3Params::new(blake3::OUT_LEN);
+        blake3.mode = Blake3Mode::Keyed([0u8; blake3::KEY_LEN]);
+        assert!(Algorithm::Sha256.merge_configured(&Algorithm::Blake3(blake3)).is_err());
+    }
+
+    #[test]
+    fn hmac_line_is_merged_with_configured_key() {
+        let line = Algorithm::from_bsd_tag("HMAC-SHA256", 32).unwrap();
+        let merged = line.merge_configured(&hmac_sha256()).unwrap();
+        assert!(merged.is_keyed());
+        assert_eq!(merged, hmac_sha256());
+        // different inner algorithm is not merged
+        let line = Algorithm::from_bsd_tag("HMAC-SHA512", 64).unwrap();
+        assert!(line.merge_configured(&hmac_sha256()).is_err());
+    }
+
+    #[test]
+    fn hmac_from_manifest_requires_key() {
+        let tag = Algorithm::from_bsd_tag("HMAC-SHA256", 32).unwrap();
+        assert!(tag.requires_key());
+        assert!(!tag.is_keyed());
+        let spec = Algorithm::from_spec("hmac-sha2-256").unwrap();
+        assert!(spec.requires_key());
+        // unkeyed configured algorithm leaves the key unset
+        assert!(tag.merge_configured(&Algorithm::Sha256).unwrap().requires_key());
+        assert!(!hmac_sha256().requires_key());
+        // hasher is never created with empty key
+        assert!(tag.create_hasher().is_err());
+        assert!(spec.create_hasher().is_err());
+        assert!(hmac_sha256().create_hasher().is_ok());
+    }
+
+    #[test]
+    fn invalid_params_are_rejected_by_create_hasher() {
+        let mut blake2 = Blake2Params::new(Blake2Variant::B);
+        blake2.outputlength = 0;
+        assert!(Algorithm::Blake2(blake2.clone()).create_hasher().is_err());
+        blake2.outputlength = 65;
+        assert!(Algorithm::Blake2(blake2).create_hasher().is_err());
+        let mut blake2 = Blake2Params::new(Blake2Variant::S);
+        blake2.key = vec![0u8; 33];
+        assert!(Algorithm::Blake2(blake2.clone()).create_hasher().is_err());
+        blake2.key = Vec::new();
+        blake2.salt = vec![0u8; 9];
+        assert!(Algorithm::Blake2(blake2).create_hasher().is_err());
+        let mut blake2 = Blake2Params::new(Blake2Variant::Bp);
+        blake2.personal = b"p".to_vec();
+        assert!(Algorithm::Blake2(blake2).create_hasher().is_err());
+        assert!(Algorithm::Blake3(Blake3Params::new(0)).create_hasher().is_err());
+        assert!(Algorithm::Blake3(Blake3Params::new(1)).create_hasher().is_ok());
+    }
+
+    #[test]
+    fn crc32_bsd_tag_matches_cksum() {
+        assert_eq!(Algorithm::Crc32.bsd_tag(), "CRC32B");
+        assert_eq!(Algorithm::from_bsd_tag("CRC32B", 4), Some(Algorithm::Crc32));
+        assert_eq!(Algorithm::from_bsd_tag("CRC32", 4), Some(Algorithm::Crc32));
+        assert_eq!(Algorithm::from_bsd_tag("CRC32C", 4), Some(Algorithm::Crc32c));
+    }
+
+    #[test]
+    fn blake3_multithread_matches_single_thread() {
+        let data: Vec<u8> = (0..3 * BLAKE3_MULTITHREAD_BUFFER_SIZE + 12345)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let mut single = Algorithm::Blake3(Blake3Params::new(32)).create_hasher().unwrap();
+        single.update(&data);
+        let expected = single.finalize_reset();
+        let mut params = Blake3Params::new(32);
+        params.multithread = true;
+        let algorithm = Algorithm::Blake3(params);
+        // small writes, one large write, and large writes after a partial buffer
+        for chunk in &[1000, data.len(), BLAKE3_MULTITHREAD_BUFFER_SIZE + 7] {
+            let mut hasher = algorithm.create_hasher().unwrap();
+            hasher.update(&data[..10]);
+            for c in data[10..].chunks(*chunk) {
+                hasher.update(c);
+            }
+            assert_eq!(hasher.finalize_reset(), expected);
+        }
+    }
+}
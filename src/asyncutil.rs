@@ -0,0 +1,165 @@
+//! async hashing for tokio, enabled by `async` feature
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), hast::ApplicationError> {
+//! let algorithm = hast::Algorithm::from_name("sha2-256", 0)?;
+//! let mut hasher = algorithm.create_hasher()?;
+//! let mut f = tokio::io::empty();
+//! hast::asyncutil::update_digest(&mut hasher, &mut f, "empty").await?;
+//! # Ok(())
+//! # }
+//! ```
+use super::error::ApplicationError;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+
+/// async version of digestutil::update_digest, feed whole content of in_f to d
+pub async fn update_digest<D, R>(d: &mut D, in_f: &mut R, inputfile: &str) -> Result<(), ApplicationError>
+where
+    D: digest::Update,
+    R: AsyncRead + Unpin,
+{
+    let mut buf: Vec<u8> = vec![0u8; 65536];
+    loop {
+        let n = match in_f.read(&mut buf).await {
+            Ok(v) => Ok(v),
+            Err(e) => Err(ApplicationError::from_io(e, inputfile, "failed to read file")),
+        }?;
+        if n == 0 {
+            break;
+        }
+        d.update(&buf[0..n]);
+    }
+    Ok(())
+}
+
+/// AsyncWrite adapter which hashes bytes as they are written to the inner writer
+///
+/// only the bytes accepted by the inner writer are hashed, so partial writes are handled correctly.
+pub struct HashingWriter<W, D> {
+    inner: W,
+    d: D,
+}
+
+impl<W, D> HashingWriter<W, D>
+where
+    W: AsyncWrite + Unpin,
+    D: digest::Update + Unpin,
+{
+    pub fn new(inner: W, d: D) -> HashingWriter<W, D> {
+        HashingWriter { inner, d }
+    }
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+    pub fn hasher_mut(&mut self) -> &mut D {
+        &mut self.d
+    }
+    /// returns the inner writer and the hasher, call finalize on the hasher to get the digest
+    pub fn into_inner(self) -> (W, D) {
+        (self.inner, self.d)
+    }
+}
+
+impl<W, D> AsyncWrite for HashingWriter<W, D>
+where
+    W: AsyncWrite + Unpin,
+    D: digest::Update + Unpin,
+{
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        let ret = Pin::new(&mut this.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = ret {
+            this.d.update(&buf[0..n]);
+        }
+        ret
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+
+    fn sha256(data: &[u8]) -> Vec<u8> {
+        let mut d = sha2::Sha256::default();
+        digest::Update::update(&mut d, data);
+        digest::Digest::finalize(d).to_vec()
+    }
+
+    /// accepts at most 3 bytes per write, and returns Pending on every other call
+    struct ShortWriter {
+        written: Vec<u8>,
+        pending: bool,
+    }
+
+    impl AsyncWrite for ShortWriter {
+        fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+            let this = self.get_mut();
+            this.pending = !this.pending;
+            if this.pending {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            let n = buf.len().min(3);
+            this.written.extend_from_slice(&buf[0..n]);
+            Poll::Ready(Ok(n))
+        }
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn test_data() -> Vec<u8> {
+        (0..200_000).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn update_digest_matches_sync() {
+        let data = test_data();
+        let mut d = sha2::Sha256::default();
+        let mut in_f: &[u8] = &data;
+        update_digest(&mut d, &mut in_f, "test").await.unwrap();
+        assert_eq!(digest::Digest::finalize(d).to_vec(), sha256(&data));
+        let mut d = sha2::Sha256::default();
+        crate::digestutil::update_digest(&mut d, &mut data.as_slice(), "test").unwrap();
+        assert_eq!(digest::Digest::finalize(d).to_vec(), sha256(&data));
+    }
+
+    #[tokio::test]
+    async fn hashing_writer_hashes_accepted_bytes() {
+        let data = test_data();
+        let inner = ShortWriter {
+            written: Vec::new(),
+            pending: false,
+        };
+        let mut w = HashingWriter::new(inner, sha2::Sha256::default());
+        // single write is accepted partially
+        let n = w.write(&data[0..10]).await.unwrap();
+        assert_eq!(n, 3);
+        w.write_all(&data[n..]).await.unwrap();
+        w.shutdown().await.unwrap();
+        let (inner, d) = w.into_inner();
+        assert_eq!(inner.written, data);
+        assert_eq!(digest::Digest::finalize(d).to_vec(), sha256(&data));
+
+        let inner = ShortWriter {
+            written: Vec::new(),
+            pending: false,
+        };
+        let mut w = HashingWriter::new(inner, sha2::Sha256::default());
+        assert_eq!(w.write(&data[0..10]).await.unwrap(), 3);
+        let (_, d) = w.into_inner();
+        assert_eq!(digest::Digest::finalize(d).to_vec(), sha256(&data[0..3]));
+    }
+}
@@ -1,216 +1,298 @@
-use super::digestutil;
+use super::algorithm::{Algorithm, Blake2Params, Blake2Variant, Blake3Mode, Blake3Params, MultiHasher};
+use super::digestutil::{self, Encoding, ReadOptions};
 use super::error::ApplicationError;
 use super::ioutil;
+use super::manifest::{FileInfo, ManifestFormat, ManifestHeader, ManifestWriter};
+use super::parallel;
+use super::walk::{self, WalkOptions};
 use clap::ArgMatches;
-use digest::Digest;
-use std::io::Write;
 
-fn normalize_path_separator<'a>(input: &'a str) -> String {
-    input.replace("\\", "/")
+/// get number of worker threads from "jobs" argument, 0 means number of available cores
+pub fn jobs_from_matches(matches: &ArgMatches) -> Result<usize, ApplicationError> {
+    let jobs = super::do_parse::<usize>(matches.value_of("jobs").unwrap_or("1"))?;
+    Ok(parallel::resolve_jobs(jobs))
 }
 
-fn write_calc_result_to_csv_output<W>(
-    data: &[u8],
-    out_f: &mut csv::Writer<W>,
-    inputfile: &str,
-    outputfile: &str,
-) -> Result<(), ApplicationError>
-where
-    W: Write,
-{
-    let mut wstr = String::new();
-    for b in data {
-        wstr.push_str(format!("{:02x}", b).as_str());
-    }
-    match out_f.write_record(&[normalize_path_separator(inputfile).as_str(), wstr.as_str()]) {
-        Ok(_) => Ok(()),
-        Err(e) => Err(ApplicationError::from_csv(
-            e,
-            format!("failed to write result({})", outputfile).as_str(),
+/// parse size like "64K" or "1M" into bytes
+fn parse_size(s: &str) -> Result<usize, ApplicationError> {
+    let (digits, unit) = match s.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => (&s[..s.len() - 1], 1024),
+        Some('M') => (&s[..s.len() - 1], 1024 * 1024),
+        Some('G') => (&s[..s.len() - 1], 1024 * 1024 * 1024),
+        _ => (s, 1),
+    };
+    match super::do_parse::<usize>(digits)?.checked_mul(unit) {
+        Some(v) if v != 0 => Ok(v),
+        _ => Err(ApplicationError::from_parameter(
+            "buffer-size",
+            format!("buffer size must be between 1 and {}({})", usize::MAX, s).as_str(),
         )),
-    }?;
-    Ok(())
-}
-
-fn do_calc_fixed_output<D>(
-    matches: &ArgMatches,
-    mut d: D,
-    outputfile: &str,
-) -> Result<(), ApplicationError>
-where
-    D: digest::FixedOutput + digest::Update + digest::Digest,
-{
-    let out_f = ioutil::create_file_for_write(outputfile)?;
-    let mut out_f = csv::Writer::from_writer(out_f);
+    }
+}
+
+pub fn read_options_from_matches(matches: &ArgMatches) -> Result<ReadOptions, ApplicationError> {
+    Ok(ReadOptions {
+        buffer_size: parse_size(matches.value_of("buffer-size").unwrap_or("64K"))?,
+        mmap: matches.is_present("mmap"),
+        read_ahead: matches.is_present("read-ahead"),
+    })
+}
+
+pub fn patterns_from_matches(matches: &ArgMatches, name: &str) -> Result<Vec<glob::Pattern>, ApplicationError> {
+    walk::parse_patterns(matches.values_of(name).into_iter().flatten())
+}
+
+fn walk_options_from_matches(matches: &ArgMatches) -> Result<WalkOptions, ApplicationError> {
+    let max_depth = match matches.value_of("max-depth") {
+        Some(v) => Some(super::do_parse::<usize>(v)?),
+        None => None,
+    };
+    Ok(WalkOptions {
+        recursive: matches.is_present("recursive"),
+        includes: patterns_from_matches(matches, "include")?,
+        excludes: patterns_from_matches(matches, "exclude")?,
+        max_depth,
+        follow_symlinks: matches.is_present("follow-symlinks"),
+        one_file_system: matches.is_present("one-file-system"),
+        respect_ignore: matches.is_present("respect-ignore"),
+    })
+}
+
+fn normalize_path_separator(input: &str) -> String {
+    if std::path::MAIN_SEPARATOR == '\\' {
+        input.replace("\\", "/")
+    } else {
+        input.to_owned()
+    }
+}
+
+pub fn expand_input_files(matches: &ArgMatches) -> Result<Vec<String>, ApplicationError> {
+    let walkoptions = walk_options_from_matches(matches)?;
+    let mut ret = Vec::new();
     if let Some(vals) = matches.values_of("file") {
         for inputfile in vals {
-            if inputfile != "-" {
-                let globresult = match glob::glob(inputfile) {
+            if inputfile == "-" {
+                ret.push(inputfile.to_owned());
+                continue;
+            }
+            let globresult = match glob::glob(inputfile) {
+                Ok(v) => Ok(v),
+                Err(e) => Err(ApplicationError::from_glob_pattern_error(
+                    e,
+                    format!("failed to parse glob({})", inputfile).as_str(),
+                )),
+            }?;
+            for p in globresult {
+                let p = match p {
                     Ok(v) => Ok(v),
-                    Err(e) => Err(ApplicationError::from_glob_pattern_error(
+                    Err(e) => Err(ApplicationError::from_glob_error(
                         e,
-                        format!("failed to parse glob({})", inputfile).as_str(),
+                        "failed to get globbed path",
                     )),
                 }?;
-                for p1 in globresult {
-                    let p = match p1 {
-                        Ok(v) => Ok(v),
-                        Err(e) => Err(ApplicationError::from_glob_error(
-                            e,
-                            "failed to get globbed path",
-                        )),
-                    }?;
-                    if let Some(p) = p.to_str() {
-                        let mut in_f = ioutil::get_file_or_stdin(p)?;
-                        digestutil::update_digest(&mut d, &mut in_f)?;
-                        let bytes: Vec<u8> = d.finalize_reset().to_vec();
-                        write_calc_result_to_csv_output(&bytes, &mut out_f, p, outputfile)?;
-                    } else {
+                if p.is_dir() {
+                    ret.extend(walkoptions.walk_dir(&p)?);
+                    continue;
+                }
+                if !walkoptions.accepts_file(&p) {
+                    continue;
+                }
+                match p.to_str() {
+                    Some(v) => ret.push(v.to_owned()),
+                    None => {
                         return Err(ApplicationError::from_path_error(
                             p.as_path(),
                             "failed to extract path string",
-                        ));
+                        ))
                     }
                 }
-            } else {
-                let mut in_f = ioutil::get_file_or_stdin(inputfile)?;
-                digestutil::update_digest(&mut d, &mut in_f)?;
-                let bytes: Vec<u8> = d.finalize_reset().to_vec();
-                write_calc_result_to_csv_output(&bytes, &mut out_f, inputfile, outputfile)?;
             }
         }
     }
-    Ok(())
+    Ok(ret)
+}
+
+pub fn calc_file(
+    inputfile: &str,
+    algorithms: &[Algorithm],
+    encoding: Encoding,
+    options: &ReadOptions,
+) -> Result<(Vec<(Algorithm, String)>, FileInfo), ApplicationError> {
+    let mut hasher = MultiHasher::new(algorithms)?;
+    let info = FileInfo::from_path(inputfile);
+    digestutil::update_digest_from_path(&mut hasher, inputfile, options)?;
+    let hashes = algorithms
+        .iter()
+        .cloned()
+        .zip(hasher.finalize_reset().iter().map(|h| encoding.encode(h)))
+        .collect();
+    Ok((hashes, info))
+}
+
+/// read hmac key from the file or environment variable,
+/// key is never accepted as command line argument to avoid leaking it in process list
+fn hmac_key_from_matches(matches: &ArgMatches) -> Result<Option<Vec<u8>>, ApplicationError> {
+    if let Some(keyfile) = matches.value_of("hmac-key-file") {
+        return Ok(Some(ioutil::read_key_file(keyfile)?));
+    }
+    if let Some(name) = matches.value_of("hmac-key-env") {
+        return match std::env::var(name) {
+            Ok(v) => Ok(Some(v.into_bytes())),
+            Err(_) => Err(ApplicationError::from_parameter(
+                "hmac-key-env",
+                format!("environment variable is not set or not valid unicode({})", name).as_str(),
+            )),
+        };
+    }
+    Ok(None)
+}
+
+/// wrap algorithms with HMAC if key is specified
+pub fn apply_hmac(matches: &ArgMatches, algorithms: Vec<Algorithm>) -> Result<Vec<Algorithm>, ApplicationError> {
+    match hmac_key_from_matches(matches)? {
+        Some(key) => algorithms.into_iter().map(|a| a.with_hmac(key.clone())).collect(),
+        None => Ok(algorithms),
+    }
+}
+
+fn do_calc(matches: &ArgMatches, algorithms: &[Algorithm]) -> Result<(), ApplicationError> {
+    let algorithms = &apply_hmac(matches, algorithms.to_vec())?;
+    let outputfile = matches.value_of("output").unwrap_or("-");
+    let format = ManifestFormat::from_name(matches.value_of("format").unwrap_or("csv"))?;
+    let encoding = Encoding::from_name(matches.value_of("encoding").unwrap_or("hex"))?;
+    let jobs = jobs_from_matches(matches)?;
+    let options = read_options_from_matches(matches)?;
+    let out_f = ioutil::create_file_for_write(outputfile)?;
+    let header = if matches.is_present("header") {
+        Some(ManifestHeader::new(algorithms)?)
+    } else {
+        None
+    };
+    let mut out_f = ManifestWriter::new(
+        out_f,
+        format,
+        matches.is_present("binary"),
+        outputfile,
+        header.as_ref(),
+    )?;
+    let inputfiles = expand_input_files(matches)?;
+    parallel::for_each_ordered(
+        &inputfiles,
+        jobs,
+        |inputfile| calc_file(inputfile, algorithms, encoding, &options),
+        |inputfile, result| {
+            let (hashes, info) = result?;
+            out_f.write_entry(&normalize_path_separator(inputfile), &hashes, &info)
+        },
+    )?;
+    out_f.finish()
 }
 
 pub fn do_calc_sha1(matches: &ArgMatches) -> Result<(), ApplicationError> {
-    let outputfile = matches.value_of("output").unwrap_or_else(|| "-");
-    do_calc_fixed_output(matches, sha1::Sha1::new(), outputfile)?;
-    Ok(())
+    do_calc(matches, &[Algorithm::Sha1])
 }
 
 pub fn do_calc_sha2(matches: &ArgMatches) -> Result<(), ApplicationError> {
-    let outputfile = matches.value_of("output").unwrap_or_else(|| "-");
-    let bitlength = matches.value_of("length").unwrap_or_else(|| "256");
-    match bitlength {
-        "224" => do_calc_fixed_output(matches, sha2::Sha224::new(), outputfile),
-        "256" => do_calc_fixed_output(matches, sha2::Sha256::new(), outputfile),
-        "384" => do_calc_fixed_output(matches, sha2::Sha384::new(), outputfile),
-        "512" => do_calc_fixed_output(matches, sha2::Sha512::new(), outputfile),
-        "512/224" => do_calc_fixed_output(matches, sha2::Sha512Trunc224::new(), outputfile),
-        "512/256" => do_calc_fixed_output(matches, sha2::Sha512Trunc256::new(), outputfile),
-        _ => Err(ApplicationError::from_parameter(
-            "length",
-            format!("invalid length parameter({})", bitlength).as_str(),
-        )),
-    }?;
-    Ok(())
+    let bitlength = matches.value_of("length").unwrap_or("256");
+    do_calc(matches, &[Algorithm::from_sha2_length(bitlength)?])
 }
 
 pub fn do_calc_md5(matches: &ArgMatches) -> Result<(), ApplicationError> {
-    let outputfile = matches.value_of("output").unwrap_or_else(|| "-");
-    do_calc_fixed_output(matches, md5::Md5::new(), outputfile)?;
-    Ok(())
+    do_calc(matches, &[Algorithm::Md5])
 }
 
 pub fn do_calc_sha3(matches: &ArgMatches) -> Result<(), ApplicationError> {
-    let outputfile = matches.value_of("output").unwrap_or_else(|| "-");
-    let bitlength = matches.value_of("length").unwrap_or_else(|| "256");
-    match bitlength {
-        "224" => do_calc_fixed_output(matches, sha3::Sha3_224::new(), outputfile),
-        "256" => do_calc_fixed_output(matches, sha3::Sha3_256::new(), outputfile),
-        "384" => do_calc_fixed_output(matches, sha3::Sha3_384::new(), outputfile),
-        "512" => do_calc_fixed_output(matches, sha3::Sha3_512::new(), outputfile),
-        _ => Err(ApplicationError::from_parameter(
-            "length",
-            format!("invalid length parameter({})", bitlength).as_str(),
-        )),
-    }?;
-    Ok(())
-}
-
-fn do_calc_extendable_output<D>(
-    matches: &ArgMatches,
-    mut d: D,
-    outputsize: usize,
-    outputfile: &str,
-) -> Result<(), ApplicationError>
-where
-    D: digest::ExtendableOutput + digest::Update,
-{
-    let out_f = ioutil::create_file_for_write(outputfile)?;
-    let mut out_f = csv::Writer::from_writer(out_f);
-    if let Some(vals) = matches.values_of("file") {
-        for inputfile in vals {
-            if inputfile != "-" {
-                let globresult = match glob::glob(inputfile) {
-                    Ok(v) => Ok(v),
-                    Err(e) => Err(ApplicationError::from_glob_pattern_error(
-                        e,
-                        format!("failed to parse glob({})", inputfile).as_str(),
-                    )),
-                }?;
-                for p in globresult {
-                    let p = match p {
-                        Ok(v) => Ok(v),
-                        Err(e) => Err(ApplicationError::from_glob_error(
-                            e,
-                            "failed to get globbed path",
-                        )),
-                    }?;
-                    if let Some(p) = p.as_path().to_str() {
-                        let mut in_f = ioutil::get_file_or_stdin(p)?;
-                        digestutil::update_digest(&mut d, &mut in_f)?;
-                        let bytes = d.finalize_boxed_reset(outputsize);
-                        write_calc_result_to_csv_output(&bytes, &mut out_f, p, outputfile)?;
-                    } else {
-                        return Err(ApplicationError::from_path_error(
-                            p.as_path(),
-                            "failed to tostring from path",
-                        ));
-                    }
-                }
-            } else {
-                let mut in_f = ioutil::get_file_or_stdin(inputfile)?;
-                digestutil::update_digest(&mut d, &mut in_f)?;
-                let bytes = d.finalize_boxed_reset(outputsize);
-                write_calc_result_to_csv_output(&bytes, &mut out_f, inputfile, outputfile)?;
-            }
-        }
-    }
-    Ok(())
+    let bitlength = matches.value_of("length").unwrap_or("256");
+    do_calc(matches, &[Algorithm::from_sha3_length(bitlength)?])
 }
 
 pub fn do_calc_shake(matches: &ArgMatches) -> Result<(), ApplicationError> {
-    let outputfile = matches.value_of("output").unwrap_or_else(|| "-");
-    let bitlength = matches.value_of("length").unwrap_or_else(|| "128");
-    let outlength =
-        super::do_parse::<usize>(matches.value_of("outputlength").unwrap_or_else(|| "128"))?;
-    match bitlength {
-        "128" => {
-            do_calc_extendable_output(matches, sha3::Shake128::default(), outlength, outputfile)
-        }
-        "256" => {
-            do_calc_extendable_output(matches, sha3::Shake256::default(), outlength, outputfile)
-        }
-        _ => Err(ApplicationError::from_parameter(
-            "length",
-            format!("invalid length parameter({})", bitlength).as_str(),
-        )),
-    }?;
-    Ok(())
+    let bitlength = matches.value_of("length").unwrap_or("128");
+    let outlength = super::do_parse::<usize>(matches.value_of("outputlength").unwrap_or("128"))?;
+    do_calc(matches, &[Algorithm::from_shake_length(bitlength, outlength)?])
+}
+
+pub fn blake2_algorithm(matches: &ArgMatches) -> Result<Algorithm, ApplicationError> {
+    let variant = Blake2Variant::from_name(matches.value_of("algorithm").unwrap_or("b"))?;
+    let mut params = Blake2Params::new(variant);
+    if let Some(outlength) = matches.value_of("outputlength") {
+        params.outputlength = super::do_parse::<usize>(outlength)?;
+    }
+    if let Some(keyfile) = matches.value_of("key-file") {
+        params.key = ioutil::read_key_file(keyfile)?;
+    }
+    if let Some(salt) = matches.value_of("salt") {
+        params.salt = salt.as_bytes().to_vec();
+    }
+    if let Some(personal) = matches.value_of("personal") {
+        params.personal = personal.as_bytes().to_vec();
+    }
+    params.validate()?;
+    Ok(Algorithm::Blake2(params))
 }
 
 pub fn do_calc_blake2(matches: &ArgMatches) -> Result<(), ApplicationError> {
-    let outputfile = matches.value_of("output").unwrap_or_else(|| "-");
-    let algorithm = matches.value_of("algorithm").unwrap_or_else(|| "b");
-    match algorithm {
-        "b" => do_calc_fixed_output(matches, blake2::Blake2b::new(), outputfile),
-        "s" => do_calc_fixed_output(matches, blake2::Blake2s::new(), outputfile),
-        _ => Err(ApplicationError::from_parameter(
-            "algorithm",
-            format!("Unknown algorithm({})", algorithm).as_str(),
-        )),
-    }?;
-    Ok(())
+    do_calc(matches, &[blake2_algorithm(matches)?])
+}
+
+pub fn blake3_algorithm(matches: &ArgMatches) -> Result<Algorithm, ApplicationError> {
+    let outlength = super::do_parse::<usize>(matches.value_of("outputlength").unwrap_or("32"))?;
+    let mut params = Blake3Params::new(outlength);
+    params.validate()?;
+    params.multithread = matches.is_present("multithread");
+    if let Some(keyfile) = matches.value_of("key-file") {
+        let key = ioutil::read_key_file(keyfile)?;
+        if key.len() != blake3::KEY_LEN {
+            return Err(ApplicationError::from_parameter(
+                "key-file",
+                format!("blake3 key must be {} bytes, but {} bytes", blake3::KEY_LEN, key.len()).as_str(),
+            ));
+        }
+        let mut buf = [0u8; blake3::KEY_LEN];
+        buf.copy_from_slice(&key);
+        params.mode = Blake3Mode::Keyed(buf);
+    } else if let Some(context) = matches.value_of("derive-key") {
+        params.mode = Blake3Mode::DeriveKey(context.to_owned());
+    }
+    Ok(Algorithm::Blake3(params))
+}
+
+pub fn do_calc_blake3(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_calc(matches, &[blake3_algorithm(matches)?])
+}
+
+pub fn do_calc_crc(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    let algorithm = matches.value_of("algorithm").unwrap_or("crc32");
+    do_calc(matches, &[Algorithm::from_crc_variant(algorithm)?])
+}
+
+pub fn do_calc_adler32(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_calc(matches, &[Algorithm::Adler32])
+}
+
+pub fn xxhash_algorithm(matches: &ArgMatches) -> Result<Algorithm, ApplicationError> {
+    let length = matches.value_of("length").unwrap_or("64");
+    let seed = super::do_parse::<u64>(matches.value_of("seed").unwrap_or("0"))?;
+    Algorithm::from_xxhash_length(length, seed)
+}
+
+pub fn do_calc_xxhash(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_calc(matches, &[xxhash_algorithm(matches)?])
+}
+
+pub fn do_calc_multi(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    let outlength = super::do_parse::<usize>(matches.value_of("outputlength").unwrap_or("128"))?;
+    let mut algorithms = Vec::new();
+    if let Some(vals) = matches.values_of("algorithms") {
+        for name in vals {
+            algorithms.push(Algorithm::from_name(name, outlength)?);
+        }
+    }
+    if algorithms.is_empty() {
+        return Err(ApplicationError::from_parameter(
+            "algorithms",
+            "no algorithm specified",
+        ));
+    }
+    do_calc(matches, &algorithms)
 }
@@ -1,173 +1,243 @@
-use super::ioutil;
+use super::algorithm::Algorithm;
 use super::error::ApplicationError;
-use digest::Digest;
+use super::ioutil;
+use super::manifest::{self, CheckOptions, CheckStatus, ManifestFormat};
+use super::walk::WalkOptions;
 use clap::ArgMatches;
-use super::digestutil;
-
-fn specialize_path_separator(input: &str) -> String {
-    input.replace("/", std::path::MAIN_SEPARATOR.to_string().as_str())
-}
-
-fn check_hash_fixed<D>(basepath: &str, inputfile: &str, expected_hash: &str, d: &mut D) -> Result<(), ApplicationError> where D: digest::Digest + digest::Update {
-    let filepath = if inputfile == "-" {
-        "-".to_owned()
-    } else {
-        let mut p = std::path::PathBuf::new();
-        p.push(basepath);
-        p.push(specialize_path_separator(inputfile));
-        match p.to_str() {
-            Some(v) => v.to_owned(),
-            None => return Err(ApplicationError::from_parameter("filename", format!("filename combine error({}, {})", basepath, inputfile).as_str()))
-        }.to_owned()
-    };
-    let mut in_f = ioutil::get_file_or_stdin(filepath.as_str())?;
-    digestutil::update_digest(d, &mut in_f)?;
-    let hash = d.finalize_reset();
-    let mut hashstr = String::new();
-    for b in hash {
-        hashstr.push_str(format!("{:02x}", b).as_str());
-    }
-    if expected_hash != hashstr {
-        return Err(ApplicationError::from_check("hash check failed", &inputfile, &filepath, &expected_hash, &hashstr));
-    }
-    Ok(())
+use serde::Serialize;
+
+#[derive(PartialEq, Eq)]
+enum ReportMode {
+    /// print all results
+    Normal,
+    /// do not print OK lines
+    Quiet,
+    /// print nothing, result is only returned by exit status
+    Status,
 }
 
-fn do_check_hash_fixed_from_csv<D>(mut d: D, inputfile: &str, basepath: &str) -> Result<(), ApplicationError> where D: digest::Digest + digest::Update {
-    let in_f = ioutil::get_file_or_stdin(inputfile)?;
-    let mut in_f = csv::Reader::from_reader(in_f);
-    for record in in_f.records() {
-        match record {
-            Ok(v) => {
-                if v.len() >= 2 {
-                    check_hash_fixed(basepath, &v[0], &v[1], &mut d)
+#[derive(PartialEq, Eq)]
+enum ReportFormat {
+    /// `path: STATUS` lines
+    Text,
+    /// array of CheckRecord
+    Json,
+    /// CheckRecord per line
+    Jsonl,
+}
+
+#[derive(Serialize)]
+struct CheckRecord<'a> {
+    path: &'a str,
+    status: &'static str,
+    algorithm: Option<String>,
+    expected: Option<&'a str>,
+    actual: Option<&'a str>,
+    /// kind of ApplicationError for unreadable file
+    error: Option<&'static str>,
+    message: Option<String>,
+}
+
+struct CheckReporter {
+    mode: ReportMode,
+    format: ReportFormat,
+    /// no record has been printed yet, used for separator of json array
+    first: bool,
+    ok: usize,
+    failed: usize,
+    missing: usize,
+    unreadable: usize,
+    extra: usize,
+}
+
+impl CheckReporter {
+    fn new(matches: &ArgMatches) -> CheckReporter {
+        let format = match matches.value_of("report-format").unwrap_or("text") {
+            "json" => ReportFormat::Json,
+            "jsonl" => ReportFormat::Jsonl,
+            _ => ReportFormat::Text,
+        };
+        let mode = if matches.is_present("status") {
+            ReportMode::Status
+        } else if matches.is_present("quiet") {
+            ReportMode::Quiet
+        } else {
+            ReportMode::Normal
+        };
+        CheckReporter {
+            mode,
+            format,
+            first: true,
+            ok: 0,
+            failed: 0,
+            missing: 0,
+            unreadable: 0,
+            extra: 0,
+        }
+    }
+    /// expected hash and algorithm are None for extra files
+    fn report(&mut self, path: &str, expected: Option<&str>, algorithm: Option<&Algorithm>, status: CheckStatus) {
+        let mut record = CheckRecord {
+            path,
+            status: "",
+            algorithm: algorithm.map(|a| a.to_spec()),
+            expected,
+            actual: None,
+            error: None,
+            message: None,
+        };
+        let line = match &status {
+            CheckStatus::Ok => {
+                self.ok += 1;
+                if self.mode != ReportMode::Normal {
+                    return;
+                }
+                record.status = "ok";
+                record.actual = expected;
+                format!("{}: OK", path)
+            }
+            CheckStatus::Failed(e) => {
+                self.failed += 1;
+                record.status = "failed";
+                record.actual = Some(e.actual());
+                format!("{}: FAILED", path)
+            }
+            CheckStatus::Missing => {
+                self.missing += 1;
+                record.status = "missing";
+                record.error = Some("io");
+                format!("{}: MISSING", path)
+            }
+            CheckStatus::Unreadable(e) => {
+                self.unreadable += 1;
+                record.status = "unreadable";
+                record.error = Some(e.kind());
+                record.message = Some(e.full_message());
+                format!("{}: UNREADABLE({})", path, e.full_message())
+            }
+            CheckStatus::Extra => {
+                self.extra += 1;
+                record.status = "extra";
+                format!("{}: EXTRA", path)
+            }
+        };
+        if self.mode == ReportMode::Status {
+            return;
+        }
+        match self.format {
+            ReportFormat::Text => println!("{}", line),
+            ReportFormat::Json | ReportFormat::Jsonl => {
+                // serializing struct of strings never fails
+                let record = serde_json::to_string(&record).expect("failed to serialize json");
+                if self.format == ReportFormat::Jsonl {
+                    println!("{}", record);
+                } else if self.first {
+                    print!("[\n{}", record);
                 } else {
-                    Ok(())
+                    print!(",\n{}", record);
                 }
-            },
-            Err(e) => {
-                Err(ApplicationError::from_csv(e, format!("failed to read csv file({})", inputfile).as_str()))
+                self.first = false;
             }
-        }?;
+        }
+    }
+    fn finish(self) -> Result<(), ApplicationError> {
+        if self.mode != ReportMode::Status && self.format == ReportFormat::Json {
+            println!("{}", if self.first { "[]" } else { "\n]" });
+        }
+        let failed = self.failed + self.missing + self.unreadable + self.extra != 0;
+        // like coreutils, quiet mode prints nothing on success
+        if self.mode == ReportMode::Normal || (self.mode == ReportMode::Quiet && failed) {
+            eprintln!(
+                "{} ok, {} mismatched, {} missing, {} unreadable, {} extra",
+                self.ok, self.failed, self.missing, self.unreadable, self.extra
+            );
+        }
+        if failed {
+            return Err(ApplicationError::from_check_summary(
+                self.ok,
+                self.failed,
+                self.missing,
+                self.unreadable,
+                self.extra,
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn do_check(matches: &ArgMatches, algorithm: Algorithm) -> Result<(), ApplicationError> {
+    let algorithm = super::calc::apply_hmac(matches, vec![algorithm])?.remove(0);
+    check_manifest(matches, Some(algorithm))
+}
+
+/// check files in manifest, algorithm is detected for each entry if configured algorithm is None
+fn check_manifest(matches: &ArgMatches, configured: Option<Algorithm>) -> Result<(), ApplicationError> {
+    let inputfile = matches.value_of("file").unwrap_or("-");
+    let format = ManifestFormat::from_name(matches.value_of("format").unwrap_or("csv"))?;
+    let in_f = ioutil::get_file_or_stdin(inputfile)?;
+    let manifest = manifest::read_manifest(in_f, format, inputfile)?;
+    let mut options = CheckOptions::new(configured, matches.value_of("basepath").unwrap_or("."));
+    options.use_header_basepath = matches.is_present("use-header-basepath");
+    options.jobs = super::calc::jobs_from_matches(matches)?;
+    options.read = super::calc::read_options_from_matches(matches)?;
+    if matches.is_present("report-extra") {
+        options.extra = Some(WalkOptions::for_tree(
+            super::calc::patterns_from_matches(matches, "pattern")?,
+            matches.is_present("respect-ignore"),
+        ));
     }
-    Ok(())
+    let mut reporter = CheckReporter::new(matches);
+    manifest::check_manifest(manifest, inputfile, &options, |path, expected, algorithm, status| {
+        reporter.report(path, expected, algorithm, status)
+    })?;
+    reporter.finish()
+}
+
+pub fn do_check_auto(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    check_manifest(matches, None)
 }
 
 pub fn do_check_sha1(matches: &ArgMatches) -> Result<(), ApplicationError> {
-    let inputfile = matches.value_of("file").unwrap_or_else(|| "-");
-    let basepath = matches.value_of("basepath").unwrap_or_else(|| ".");
-    let d = sha1::Sha1::new();
-    do_check_hash_fixed_from_csv(d, inputfile, basepath)?;
-    Ok(())
+    do_check(matches, Algorithm::Sha1)
 }
 
 pub fn do_check_sha2(matches: &ArgMatches) -> Result<(), ApplicationError> {
-    let inputfile = matches.value_of("file").unwrap_or_else(|| "-");
-    let basepath = matches.value_of("basepath").unwrap_or_else(|| ".");
-    let bitlength = matches.value_of("length").unwrap_or_else(|| "256");
-    match bitlength {
-        "224" => do_check_hash_fixed_from_csv(sha2::Sha224::new(), inputfile, basepath),
-        "256" => do_check_hash_fixed_from_csv(sha2::Sha256::new(), inputfile, basepath),
-        "384" => do_check_hash_fixed_from_csv(sha2::Sha384::new(), inputfile, basepath),
-        "512" => do_check_hash_fixed_from_csv(sha2::Sha512::new(), inputfile, basepath),
-        "512/224" => do_check_hash_fixed_from_csv(sha2::Sha512Trunc224::new(), inputfile, basepath),
-        "512/256" => do_check_hash_fixed_from_csv(sha2::Sha512Trunc256::new(), inputfile, basepath),
-        _ => Err(ApplicationError::from_parameter("length", format!("invalid length parameter({})", bitlength).as_str()))
-    }?;
-    Ok(())
+    let bitlength = matches.value_of("length").unwrap_or("256");
+    do_check(matches, Algorithm::from_sha2_length(bitlength)?)
 }
 
 pub fn do_check_md5(matches: &ArgMatches) -> Result<(), ApplicationError> {
-    let inputfile = matches.value_of("file").unwrap_or_else(|| "-");
-    let basepath = matches.value_of("basepath").unwrap_or_else(|| ".");
-    let d = md5::Md5::new();
-    do_check_hash_fixed_from_csv(d, inputfile, basepath)?;
-    Ok(())
+    do_check(matches, Algorithm::Md5)
 }
 
 pub fn do_check_sha3(matches: &ArgMatches) -> Result<(), ApplicationError> {
-    let inputfile = matches.value_of("file").unwrap_or_else(|| "-");
-    let basepath = matches.value_of("basepath").unwrap_or_else(|| ".");
-    let bitlength = matches.value_of("length").unwrap_or_else(|| "256");
-    match bitlength {
-        "224" => do_check_hash_fixed_from_csv(sha3::Sha3_224::new(), inputfile, basepath),
-        "256" => do_check_hash_fixed_from_csv(sha3::Sha3_256::new(), inputfile, basepath),
-        "384" => do_check_hash_fixed_from_csv(sha3::Sha3_384::new(), inputfile, basepath),
-        "512" => do_check_hash_fixed_from_csv(sha3::Sha3_512::new(), inputfile, basepath),
-        _ => Err(ApplicationError::from_parameter("length", format!("invalid length parameter({})", bitlength).as_str()))
-    }?;
-    Ok(())
-}
-
-fn check_hash_extendable<D>(d: &mut D, basepath: &str, inputfile: &str, outputlength: usize, expected_hash: &str) -> Result<(), ApplicationError> where D: digest::ExtendableOutput + digest::Update{
-    let filepath = if inputfile == "-" {
-        "-".to_owned()
-    } else {
-        let mut p = std::path::PathBuf::new();
-        p.push(basepath);
-        p.push(inputfile);
-        match p.to_str() {
-            Some(v) => v.to_owned(),
-            None => return Err(ApplicationError::from_parameter("filename", format!("filename combine error({}, {})", basepath, inputfile).as_str()))
-        }.to_owned()
-    };
-    let mut in_f = ioutil::get_file_or_stdin(filepath.as_str())?;
-    digestutil::update_digest(d, &mut in_f)?;
-    let hash = d.finalize_boxed_reset(outputlength);
-    let mut hashstr = String::new();
-    for b in hash.into_iter() {
-        hashstr.push_str(format!("{:x}", b).as_str());
-    }
-    if expected_hash != hashstr {
-        return Err(ApplicationError::from_check("hash check failed", &inputfile, &filepath, &expected_hash, &hashstr));
-    }
-    Ok(())
-}
-
-fn do_check_hash_extendable_from_csv<D>(mut d: D, inputfile: &str, basepath: &str, outputlength: usize) -> Result<(), ApplicationError> where D: digest::ExtendableOutput + digest::Update {
-    let in_f = ioutil::get_file_or_stdin(inputfile)?;
-    let mut in_f = csv::Reader::from_reader(in_f);
-    for record in in_f.records() {
-        match record {
-            Ok(v) => {
-                if v.len() >= 2 {
-                    check_hash_extendable(&mut d, basepath, &v[0], outputlength, &v[1], )
-                } else {
-                    Ok(())
-                }
-            },
-            Err(e) => {
-                Err(ApplicationError::from_csv(e, format!("failed to read csv file({})", inputfile).as_str()))
-            }
-        }?;
-    }
-    Ok(())
+    let bitlength = matches.value_of("length").unwrap_or("256");
+    do_check(matches, Algorithm::from_sha3_length(bitlength)?)
 }
 
 pub fn do_check_shake(matches: &ArgMatches) -> Result<(), ApplicationError> {
-    let inputfile = matches.value_of("file").unwrap_or_else(|| "-");
-    let basepath = matches.value_of("basepath").unwrap_or_else(|| ".");
-    let bitlength = matches.value_of("length").unwrap_or_else(|| "128");
-    let outlength = super::do_parse::<usize>(matches.value_of("outputlength").unwrap_or_else(|| "128"))?;
-    match bitlength {
-        "128" => do_check_hash_extendable_from_csv(sha3::Shake128::default(), inputfile, basepath, outlength),
-        "256" => do_check_hash_extendable_from_csv(sha3::Shake256::default(), inputfile, basepath, outlength),
-        _ => Err(ApplicationError::from_parameter(
-            "length",
-            format!("invalid length parameter({})", bitlength).as_str(),
-        )),
-    }?;
-    Ok(())
+    let bitlength = matches.value_of("length").unwrap_or("128");
+    let outlength = super::do_parse::<usize>(matches.value_of("outputlength").unwrap_or("128"))?;
+    do_check(matches, Algorithm::from_shake_length(bitlength, outlength)?)
 }
 
 pub fn do_check_blake2(matches: &ArgMatches) -> Result<(), ApplicationError> {
-    let inputfile = matches.value_of("file").unwrap_or_else(|| "-");
-    let basepath = matches.value_of("basepath").unwrap_or_else(|| ".");
-    let algorithm = matches.value_of("algorithm").unwrap_or_else(|| "b");
-    match algorithm {
-        "b" => do_check_hash_fixed_from_csv(blake2::Blake2b::new(), inputfile, basepath),
-        "s" => do_check_hash_fixed_from_csv(blake2::Blake2s::new(), inputfile, basepath),
-        _ => Err(ApplicationError::from_parameter("algorithm", format!("Unknown algorithm({})", algorithm).as_str()))
-    }?;
-    Ok(())
-}
\ No newline at end of file
+    do_check(matches, super::calc::blake2_algorithm(matches)?)
+}
+
+pub fn do_check_blake3(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_check(matches, super::calc::blake3_algorithm(matches)?)
+}
+
+pub fn do_check_crc(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    let algorithm = matches.value_of("algorithm").unwrap_or("crc32");
+    do_check(matches, Algorithm::from_crc_variant(algorithm)?)
+}
+
+pub fn do_check_adler32(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_check(matches, Algorithm::Adler32)
+}
+
+pub fn do_check_xxhash(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_check(matches, super::calc::xxhash_algorithm(matches)?)
+}
@@ -1,6 +1,10 @@
 use clap::App;
 use clap::SubCommand;
 use clap::{Arg};
+use super::algorithm::ALGORITHM_NAMES;
+use super::digestutil::ENCODING_NAMES;
+use super::manifest::FORMAT_NAMES;
+use super::sri::SRI_ALGORITHM_NAMES;
 
 fn create_calc_file_arg<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name("file")
@@ -29,6 +33,156 @@ fn create_output_arg<'a, 'b>() -> Arg<'a, 'b> {
         .help("output file, '-' means stdout")
 }
 
+fn create_format_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("format")
+        .value_name("FORMAT")
+        .possible_values(FORMAT_NAMES)
+        .default_value("csv")
+        .long("format")
+        .help("checksum file format, 'gnu' is compatible with sha256sum and so on")
+}
+
+fn create_binary_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("binary")
+        .long("binary")
+        .help("mark files as binary('*') in gnu format output")
+}
+
+fn create_encoding_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("encoding")
+        .value_name("ENCODING")
+        .possible_values(ENCODING_NAMES)
+        .default_value("hex")
+        .long("encoding")
+        .help("digest encoding, 'HEX' is uppercase hex and 'nix32' is base32 used by Nix")
+}
+
+fn create_header_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("header")
+        .long("header")
+        .help("write metadata header(algorithm, parameters, version, creation time and base path), which is read by check")
+}
+
+fn create_report_format_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("report-format")
+        .value_name("REPORT_FORMAT")
+        .possible_values(&["text", "json", "jsonl"])
+        .default_value("text")
+        .long("report-format")
+        .help("format of check result")
+}
+
+fn create_quiet_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("quiet")
+        .long("quiet")
+        .short("q")
+        .help("don't print OK for each successfully verified file")
+}
+
+fn create_status_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("status")
+        .long("status")
+        .conflicts_with("quiet")
+        .help("don't output anything, status code shows success")
+}
+
+fn create_report_extra_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("report-extra")
+        .long("report-extra")
+        .alias("strict-tree")
+        .help("report files which exist under base path but are not listed in input file")
+}
+
+fn create_pattern_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("pattern")
+        .value_name("PATTERN")
+        .long("pattern")
+        .multiple(true)
+        .number_of_values(1)
+        .requires("report-extra")
+        .help("glob pattern(relative to base path) to filter files reported by --report-extra")
+}
+
+fn create_walk_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("recursive")
+            .short("r")
+            .long("recursive")
+            .help("hash files in directories recursively"),
+        Arg::with_name("include")
+            .value_name("PATTERN")
+            .long("include")
+            .multiple(true)
+            .number_of_values(1)
+            .help("hash only files matching the glob pattern(relative to the input directory)"),
+        Arg::with_name("exclude")
+            .value_name("PATTERN")
+            .long("exclude")
+            .multiple(true)
+            .number_of_values(1)
+            .help("skip files and directories matching the glob pattern(relative to the input directory)"),
+        Arg::with_name("max-depth")
+            .value_name("DEPTH")
+            .long("max-depth")
+            .requires("recursive")
+            .help("maximum depth of directory traversal"),
+        Arg::with_name("follow-symlinks")
+            .long("follow-symlinks")
+            .requires("recursive")
+            .help("follow symbolic links while traversing directories"),
+        Arg::with_name("one-file-system")
+            .long("one-file-system")
+            .requires("recursive")
+            .help("don't cross file system boundaries while traversing directories"),
+        create_respect_ignore_arg().requires("recursive"),
+    ]
+}
+
+fn create_respect_ignore_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("respect-ignore")
+        .long("respect-ignore")
+        .help("skip files ignored by .gitignore, .ignore and .hastignore while traversing directories")
+}
+
+fn create_jobs_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("jobs")
+        .value_name("N")
+        .short("j")
+        .long("jobs")
+        .default_value("1")
+        .help("number of files hashed concurrently, 0 means number of CPU cores")
+}
+
+fn create_read_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("buffer-size")
+            .value_name("SIZE")
+            .long("buffer-size")
+            .default_value("64K")
+            .help("read buffer size in bytes, K, M and G suffixes are allowed"),
+        Arg::with_name("mmap")
+            .long("mmap")
+            .help("hash regular files through memory map, other files are read normally"),
+        Arg::with_name("read-ahead")
+            .long("read-ahead")
+            .help("read the next buffer in another thread while hashing"),
+    ]
+}
+
+fn create_hmac_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("hmac-key-file")
+            .value_name("KEY_FILE")
+            .long("hmac-key-file")
+            .conflicts_with("hmac-key-env")
+            .help("calculate HMAC with the key in the file"),
+        Arg::with_name("hmac-key-env")
+            .value_name("ENV_NAME")
+            .long("hmac-key-env")
+            .help("calculate HMAC with the key in the environment variable"),
+    ]
+}
+
 fn create_basepath_arg<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name("basepath")
         .value_name("BASE_PATH")
@@ -38,6 +192,13 @@ fn create_basepath_arg<'a, 'b>() -> Arg<'a, 'b> {
         .help("base path for searching file")
 }
 
+fn create_use_header_basepath_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("use-header-basepath")
+        .long("use-header-basepath")
+        .conflicts_with("basepath")
+        .help("use base path recorded in manifest header instead of current directory")
+}
+
 fn create_output_length_arg<'a, 'b>(default_size: &'a str) -> Arg<'a, 'b> {
     Arg::with_name("outputlength")
         .value_name("OUTPUTLENGTH")
@@ -51,52 +212,93 @@ pub fn create_calc_sha1<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("sha1")
         .about("calc sha1 hash")
         .arg(create_calc_file_arg())
+        .args(&create_walk_args())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
         .arg(create_output_arg())
+        .arg(create_format_arg())
+        .arg(create_binary_arg())
+        .arg(create_header_arg())
+        .arg(create_encoding_arg())
+        .args(&create_hmac_args())
 }
 
 pub fn create_check_sha1<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("sha1")
         .about("check sha1 hash")
         .arg(create_check_file_arg())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
+        .arg(create_format_arg())
+        .arg(create_report_format_arg())
+        .arg(create_quiet_arg())
+        .arg(create_status_arg())
+        .arg(create_report_extra_arg())
+        .arg(create_pattern_arg())
+        .arg(create_respect_ignore_arg().requires("report-extra"))
         .arg(create_basepath_arg())
+        .arg(create_use_header_basepath_arg())
+        .args(&create_hmac_args())
+}
+
+fn create_sha2_length_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("length")
+        .help("bit length")
+        .possible_values(&["224", "256", "384", "512", "512/224", "512/256"])
+        .default_value("256")
+        .short("l")
+        .long("length")
 }
 
 pub fn create_calc_sha2<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("sha2")
         .about("calc sha2 hash")
         .arg(create_calc_file_arg())
+        .args(&create_walk_args())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
         .arg(create_output_arg())
-        .arg(
-            Arg::with_name("length")
-                .help("bit length")
-                .possible_values(&["224", "256", "384", "512", "512/224", "512/256"])
-                .default_value("256")
-                .short("l")
-                .long("length"),
-        )
+        .arg(create_format_arg())
+        .arg(create_binary_arg())
+        .arg(create_header_arg())
+        .arg(create_encoding_arg())
+        .arg(create_sha2_length_arg())
+        .args(&create_hmac_args())
 }
 
 pub fn create_check_sha2<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("sha2")
         .about("check sha2 hash")
         .arg(create_check_file_arg())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
+        .arg(create_format_arg())
+        .arg(create_report_format_arg())
+        .arg(create_quiet_arg())
+        .arg(create_status_arg())
+        .arg(create_report_extra_arg())
+        .arg(create_pattern_arg())
+        .arg(create_respect_ignore_arg().requires("report-extra"))
         .arg(create_basepath_arg())
-        .arg(
-            Arg::with_name("length")
-                .help("bit length")
-                .possible_values(&["224", "256", "384", "512", "512/224", "512/256"])
-                .default_value("256")
-                .short("l")
-                .long("length"),
-        )
+        .arg(create_use_header_basepath_arg())
+        .arg(create_sha2_length_arg())
+        .args(&create_hmac_args())
 }
 
 pub fn create_calc_sha3<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("sha3")
         .about("calc sha3 hash")
         .arg(create_calc_file_arg())
+        .args(&create_walk_args())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
         .arg(create_output_arg())
+        .arg(create_format_arg())
+        .arg(create_binary_arg())
+        .arg(create_header_arg())
+        .arg(create_encoding_arg())
         .arg(create_sha3_length_arg())
+        .args(&create_hmac_args())
 }
 
 fn create_sha3_length_arg<'a, 'b>() -> Arg<'a, 'b> {
@@ -112,22 +314,69 @@ pub fn create_check_sha3<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("sha3")
         .about("check sha3 hash")
         .arg(create_check_file_arg())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
+        .arg(create_format_arg())
+        .arg(create_report_format_arg())
+        .arg(create_quiet_arg())
+        .arg(create_status_arg())
+        .arg(create_report_extra_arg())
+        .arg(create_pattern_arg())
+        .arg(create_respect_ignore_arg().requires("report-extra"))
         .arg(create_basepath_arg())
+        .arg(create_use_header_basepath_arg())
         .arg(create_sha3_length_arg())
+        .args(&create_hmac_args())
 }
 
 pub fn create_calc_md5<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("md5")
         .about("calc md5 hash")
         .arg(create_calc_file_arg())
+        .args(&create_walk_args())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
         .arg(create_output_arg())
+        .arg(create_format_arg())
+        .arg(create_binary_arg())
+        .arg(create_header_arg())
+        .arg(create_encoding_arg())
+        .args(&create_hmac_args())
+}
+
+pub fn create_check_auto<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("auto")
+        .about("check hash, algorithm is detected from BSD style tag or digest length")
+        .arg(create_check_file_arg())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
+        .arg(create_format_arg())
+        .arg(create_report_format_arg())
+        .arg(create_quiet_arg())
+        .arg(create_status_arg())
+        .arg(create_report_extra_arg())
+        .arg(create_pattern_arg())
+        .arg(create_respect_ignore_arg().requires("report-extra"))
+        .arg(create_basepath_arg())
+        .arg(create_use_header_basepath_arg())
 }
 
 pub fn create_check_md5<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("md5")
         .about("check md5 hash")
         .arg(create_check_file_arg())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
+        .arg(create_format_arg())
+        .arg(create_report_format_arg())
+        .arg(create_quiet_arg())
+        .arg(create_status_arg())
+        .arg(create_report_extra_arg())
+        .arg(create_pattern_arg())
+        .arg(create_respect_ignore_arg().requires("report-extra"))
         .arg(create_basepath_arg())
+        .arg(create_use_header_basepath_arg())
+        .args(&create_hmac_args())
 }
 
 fn create_shake_bitlength_arg<'a, 'b>() -> Arg<'a, 'b> {
@@ -143,7 +392,14 @@ pub fn create_calc_shake<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("shake")
         .about("calc shake hash")
         .arg(create_calc_file_arg())
+        .args(&create_walk_args())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
         .arg(create_output_arg())
+        .arg(create_format_arg())
+        .arg(create_binary_arg())
+        .arg(create_header_arg())
+        .arg(create_encoding_arg())
         .arg(create_output_length_arg("128"))
         .arg(create_shake_bitlength_arg())
 }
@@ -152,30 +408,445 @@ pub fn create_check_shake<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("shake")
         .about("check shake hash")
         .arg(create_check_file_arg())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
+        .arg(create_format_arg())
+        .arg(create_report_format_arg())
+        .arg(create_quiet_arg())
+        .arg(create_status_arg())
+        .arg(create_report_extra_arg())
+        .arg(create_pattern_arg())
+        .arg(create_respect_ignore_arg().requires("report-extra"))
+        .arg(create_basepath_arg())
+        .arg(create_use_header_basepath_arg())
         .arg(create_output_length_arg("128"))
         .arg(create_shake_bitlength_arg())
 }
 
-fn create_blake2_algorithm_arg<'a, 'b>() -> Arg<'a, 'b> {
-    Arg::with_name("algorithm")
-        .help("blake2 algorithm")
-        .possible_values(&["s", "b"])
-        .short("a")
-        .long("algorithm")
-        .default_value("s")
+fn create_blake2_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("algorithm")
+            .help("blake2 algorithm, bp and sp are parallel variants")
+            .possible_values(&["s", "b", "sp", "bp"])
+            .short("a")
+            .long("algorithm")
+            .default_value("s"),
+        Arg::with_name("outputlength")
+            .value_name("OUTPUTLENGTH")
+            .short("x")
+            .long("outputlength")
+            .help("hash output length in bytes, default is 64(b, bp) or 32(s, sp)"),
+        Arg::with_name("key-file")
+            .value_name("KEY_FILE")
+            .long("key-file")
+            .help("file which contains key for keyed hash mode, up to 64(b, bp) or 32(s, sp) bytes"),
+        Arg::with_name("salt")
+            .value_name("SALT")
+            .long("salt")
+            .help("salt string, up to 16(b) or 8(s) bytes"),
+        Arg::with_name("personal")
+            .value_name("PERSONAL")
+            .long("personal")
+            .help("personalization string, up to 16(b) or 8(s) bytes"),
+    ]
 }
 
 pub fn create_calc_blake2<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("blake2")
         .about("calc blake2 hash")
         .arg(create_output_arg())
-        .arg(create_blake2_algorithm_arg())
+        .arg(create_format_arg())
+        .arg(create_binary_arg())
+        .arg(create_header_arg())
+        .arg(create_encoding_arg())
+        .args(&create_blake2_args())
         .arg(create_calc_file_arg())
+        .args(&create_walk_args())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
+        .args(&create_hmac_args())
 }
 
 pub fn create_check_blake2<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("blake2")
         .about("check blake2 hash")
         .arg(create_check_file_arg())
-        .arg(create_blake2_algorithm_arg())
-}
\ No newline at end of file
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
+        .arg(create_format_arg())
+        .arg(create_report_format_arg())
+        .arg(create_quiet_arg())
+        .arg(create_status_arg())
+        .arg(create_report_extra_arg())
+        .arg(create_pattern_arg())
+        .arg(create_respect_ignore_arg().requires("report-extra"))
+        .arg(create_basepath_arg())
+        .arg(create_use_header_basepath_arg())
+        .args(&create_blake2_args())
+        .args(&create_hmac_args())
+}
+
+pub fn create_calc_multi<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("multi")
+        .about("calc several hashes at once, reading each file only once")
+        .arg(create_calc_file_arg())
+        .args(&create_walk_args())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
+        .arg(create_output_arg())
+        .arg(create_format_arg())
+        .arg(create_binary_arg())
+        .arg(create_header_arg())
+        .arg(create_encoding_arg())
+        .arg(
+            Arg::with_name("algorithms")
+                .help("comma separated algorithm list")
+                .value_name("ALGORITHMS")
+                .possible_values(ALGORITHM_NAMES)
+                .use_delimiter(true)
+                .multiple(true)
+                .number_of_values(1)
+                .required(true)
+                .short("a")
+                .long("algorithms"),
+        )
+        .arg(create_output_length_arg("128").help("shake hash output length in bytes"))
+        .args(&create_hmac_args())
+}
+
+fn create_blake3_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        create_output_length_arg("32"),
+        Arg::with_name("key-file")
+            .value_name("KEY_FILE")
+            .long("key-file")
+            .conflicts_with("derive-key")
+            .help("file which contains 32 bytes key for keyed hash mode"),
+        Arg::with_name("derive-key")
+            .value_name("CONTEXT")
+            .long("derive-key")
+            .help("context string for key derivation mode"),
+        Arg::with_name("multithread")
+            .long("multithread")
+            .help("use multiple threads for hashing each file"),
+    ]
+}
+
+pub fn create_calc_blake3<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("blake3")
+        .about("calc blake3 hash")
+        .arg(create_calc_file_arg())
+        .args(&create_walk_args())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
+        .arg(create_output_arg())
+        .arg(create_format_arg())
+        .arg(create_binary_arg())
+        .arg(create_header_arg())
+        .arg(create_encoding_arg())
+        .args(&create_blake3_args())
+}
+
+pub fn create_check_blake3<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("blake3")
+        .about("check blake3 hash")
+        .arg(create_check_file_arg())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
+        .arg(create_format_arg())
+        .arg(create_report_format_arg())
+        .arg(create_quiet_arg())
+        .arg(create_status_arg())
+        .arg(create_report_extra_arg())
+        .arg(create_pattern_arg())
+        .arg(create_respect_ignore_arg().requires("report-extra"))
+        .arg(create_basepath_arg())
+        .arg(create_use_header_basepath_arg())
+        .args(&create_blake3_args())
+}
+
+fn create_crc_algorithm_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("algorithm")
+        .help("crc algorithm, crc32 is same as zlib, crc32c is Castagnoli, crc64 is same as xz")
+        .possible_values(&["crc32", "crc32c", "crc64"])
+        .short("a")
+        .long("algorithm")
+        .default_value("crc32")
+}
+
+pub fn create_calc_crc<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("crc")
+        .about("calc crc checksum")
+        .arg(create_calc_file_arg())
+        .args(&create_walk_args())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
+        .arg(create_output_arg())
+        .arg(create_format_arg())
+        .arg(create_binary_arg())
+        .arg(create_header_arg())
+        .arg(create_encoding_arg())
+        .arg(create_crc_algorithm_arg())
+}
+
+pub fn create_check_crc<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("crc")
+        .about("check crc checksum")
+        .arg(create_check_file_arg())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
+        .arg(create_format_arg())
+        .arg(create_report_format_arg())
+        .arg(create_quiet_arg())
+        .arg(create_status_arg())
+        .arg(create_report_extra_arg())
+        .arg(create_pattern_arg())
+        .arg(create_respect_ignore_arg().requires("report-extra"))
+        .arg(create_basepath_arg())
+        .arg(create_use_header_basepath_arg())
+        .arg(create_crc_algorithm_arg())
+}
+
+pub fn create_calc_adler32<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("adler32")
+        .about("calc adler32 checksum")
+        .arg(create_calc_file_arg())
+        .args(&create_walk_args())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
+        .arg(create_output_arg())
+        .arg(create_format_arg())
+        .arg(create_binary_arg())
+        .arg(create_header_arg())
+        .arg(create_encoding_arg())
+}
+
+pub fn create_check_adler32<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("adler32")
+        .about("check adler32 checksum")
+        .arg(create_check_file_arg())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
+        .arg(create_format_arg())
+        .arg(create_report_format_arg())
+        .arg(create_quiet_arg())
+        .arg(create_status_arg())
+        .arg(create_report_extra_arg())
+        .arg(create_pattern_arg())
+        .arg(create_respect_ignore_arg().requires("report-extra"))
+        .arg(create_basepath_arg())
+        .arg(create_use_header_basepath_arg())
+}
+
+fn create_xxhash_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("length")
+            .help("xxhash variant, 32 and 64 are XXH32 and XXH64, 3-64 and 3-128 are XXH3")
+            .possible_values(&["32", "64", "3-64", "3-128"])
+            .default_value("64")
+            .short("l")
+            .long("length"),
+        Arg::with_name("seed")
+            .value_name("SEED")
+            .help("seed value")
+            .default_value("0")
+            .long("seed"),
+    ]
+}
+
+pub fn create_calc_xxhash<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("xxhash")
+        .about("calc xxhash")
+        .arg(create_calc_file_arg())
+        .args(&create_walk_args())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
+        .arg(create_output_arg())
+        .arg(create_format_arg())
+        .arg(create_binary_arg())
+        .arg(create_header_arg())
+        .arg(create_encoding_arg())
+        .args(&create_xxhash_args())
+}
+
+pub fn create_check_xxhash<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("xxhash")
+        .about("check xxhash")
+        .arg(create_check_file_arg())
+        .arg(create_jobs_arg())
+        .args(&create_read_args())
+        .arg(create_format_arg())
+        .arg(create_report_format_arg())
+        .arg(create_quiet_arg())
+        .arg(create_status_arg())
+        .arg(create_report_extra_arg())
+        .arg(create_pattern_arg())
+        .arg(create_respect_ignore_arg().requires("report-extra"))
+        .arg(create_basepath_arg())
+        .arg(create_use_header_basepath_arg())
+        .args(&create_xxhash_args())
+}
+
+pub fn create_sri<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("sri")
+        .about("calc or verify Subresource Integrity(sha384-...) of files")
+        .arg(create_calc_file_arg())
+        .arg(
+            Arg::with_name("algorithm")
+                .value_name("ALGORITHM")
+                .possible_values(SRI_ALGORITHM_NAMES)
+                .default_value("sha384")
+                .short("a")
+                .long("algorithm")
+                .multiple(true)
+                .use_delimiter(true)
+                .number_of_values(1)
+                .help("algorithms in the integrity, can be comma separated"),
+        )
+        .arg(
+            Arg::with_name("verify")
+                .value_name("INTEGRITY")
+                .long("verify")
+                .conflicts_with("algorithm")
+                .help("verify files with the integrity string instead of calculating it"),
+        )
+}
+
+/// common part of verify subcommands
+fn create_verify_subcommand<'a, 'b>(name: &str, about: &'b str) -> App<'a, 'b> {
+    SubCommand::with_name(name)
+        .about(about)
+        .arg(
+            Arg::with_name("file")
+                .value_name("FILE")
+                .default_value("-")
+                .help("input file, '-' means stdin"),
+        )
+        .arg(
+            Arg::with_name("expect")
+                .value_name("HASH")
+                .long("expect")
+                .required(true)
+                .help("expected hash in any encoding of calc"),
+        )
+        .args(&create_read_args())
+}
+
+pub fn create_verify_md5<'a, 'b>() -> App<'a, 'b> {
+    create_verify_subcommand("md5", "verify md5 hash").args(&create_hmac_args())
+}
+
+pub fn create_verify_sha1<'a, 'b>() -> App<'a, 'b> {
+    create_verify_subcommand("sha1", "verify sha1 hash").args(&create_hmac_args())
+}
+
+pub fn create_verify_sha2<'a, 'b>() -> App<'a, 'b> {
+    create_verify_subcommand("sha2", "verify sha2 hash")
+        .arg(create_sha2_length_arg())
+        .args(&create_hmac_args())
+}
+
+pub fn create_verify_sha3<'a, 'b>() -> App<'a, 'b> {
+    create_verify_subcommand("sha3", "verify sha3 hash")
+        .arg(create_sha3_length_arg())
+        .args(&create_hmac_args())
+}
+
+pub fn create_verify_shake<'a, 'b>() -> App<'a, 'b> {
+    create_verify_subcommand("shake", "verify shake hash")
+        .arg(create_output_length_arg("128"))
+        .arg(create_shake_bitlength_arg())
+}
+
+pub fn create_verify_blake2<'a, 'b>() -> App<'a, 'b> {
+    create_verify_subcommand("blake2", "verify blake2 hash")
+        .args(&create_blake2_args())
+        .args(&create_hmac_args())
+}
+
+pub fn create_verify_blake3<'a, 'b>() -> App<'a, 'b> {
+    create_verify_subcommand("blake3", "verify blake3 hash").args(&create_blake3_args())
+}
+
+pub fn create_verify_crc<'a, 'b>() -> App<'a, 'b> {
+    create_verify_subcommand("crc", "verify crc checksum").arg(create_crc_algorithm_arg())
+}
+
+pub fn create_verify_adler32<'a, 'b>() -> App<'a, 'b> {
+    create_verify_subcommand("adler32", "verify adler32 checksum")
+}
+
+pub fn create_verify_xxhash<'a, 'b>() -> App<'a, 'b> {
+    create_verify_subcommand("xxhash", "verify xxhash").args(&create_xxhash_args())
+}
+
+/// common part of tee subcommands
+fn create_tee_subcommand<'a, 'b>(name: &str, about: &'b str) -> App<'a, 'b> {
+    SubCommand::with_name(name)
+        .about(about)
+        .arg(
+            Arg::with_name("hash-out")
+                .value_name("HASH_FILE")
+                .long("hash-out")
+                .required(true)
+                .help("file to write the hash of the passed through data"),
+        )
+        .arg(
+            Arg::with_name("name")
+                .value_name("NAME")
+                .default_value("-")
+                .long("name")
+                .help("path recorded in the hash file"),
+        )
+        .arg(create_format_arg())
+        .arg(create_binary_arg())
+        .arg(create_encoding_arg())
+}
+
+pub fn create_tee_md5<'a, 'b>() -> App<'a, 'b> {
+    create_tee_subcommand("md5", "copy stdin to stdout with md5 hash").args(&create_hmac_args())
+}
+
+pub fn create_tee_sha1<'a, 'b>() -> App<'a, 'b> {
+    create_tee_subcommand("sha1", "copy stdin to stdout with sha1 hash").args(&create_hmac_args())
+}
+
+pub fn create_tee_sha2<'a, 'b>() -> App<'a, 'b> {
+    create_tee_subcommand("sha2", "copy stdin to stdout with sha2 hash")
+        .arg(create_sha2_length_arg())
+        .args(&create_hmac_args())
+}
+
+pub fn create_tee_sha3<'a, 'b>() -> App<'a, 'b> {
+    create_tee_subcommand("sha3", "copy stdin to stdout with sha3 hash")
+        .arg(create_sha3_length_arg())
+        .args(&create_hmac_args())
+}
+
+pub fn create_tee_shake<'a, 'b>() -> App<'a, 'b> {
+    create_tee_subcommand("shake", "copy stdin to stdout with shake hash")
+        .arg(create_output_length_arg("128"))
+        .arg(create_shake_bitlength_arg())
+}
+
+pub fn create_tee_blake2<'a, 'b>() -> App<'a, 'b> {
+    create_tee_subcommand("blake2", "copy stdin to stdout with blake2 hash")
+        .args(&create_blake2_args())
+        .args(&create_hmac_args())
+}
+
+pub fn create_tee_blake3<'a, 'b>() -> App<'a, 'b> {
+    create_tee_subcommand("blake3", "copy stdin to stdout with blake3 hash").args(&create_blake3_args())
+}
+
+pub fn create_tee_crc<'a, 'b>() -> App<'a, 'b> {
+    create_tee_subcommand("crc", "copy stdin to stdout with crc checksum").arg(create_crc_algorithm_arg())
+}
+
+pub fn create_tee_adler32<'a, 'b>() -> App<'a, 'b> {
+    create_tee_subcommand("adler32", "copy stdin to stdout with adler32 checksum")
+}
+
+pub fn create_tee_xxhash<'a, 'b>() -> App<'a, 'b> {
+    create_tee_subcommand("xxhash", "copy stdin to stdout with xxhash").args(&create_xxhash_args())
+}
@@ -1,18 +1,489 @@
 use super::error::ApplicationError;
 use std::io::Read;
 
-pub fn update_digest<D, R>(d: &mut D, in_f: &mut R) -> Result<(), ApplicationError> where D: digest::Update, R: Read {
-    let mut buf: Vec<u8> = Vec::new();
-    buf.resize(1024, 0u8);
+/// default size of read buffer
+pub const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
+/// number of buffers in flight between the read-ahead thread and the hasher
+const READ_AHEAD_BUFFERS: usize = 4;
+
+/// how the input file is read while hashing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadOptions {
+    pub buffer_size: usize,
+    /// hash regular files through memory map, other files are read normally
+    pub mmap: bool,
+    /// read the next buffer in another thread while hashing the current one
+    pub read_ahead: bool,
+}
+
+impl Default for ReadOptions {
+    fn default() -> ReadOptions {
+        ReadOptions {
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            mmap: false,
+            read_ahead: false,
+        }
+    }
+}
+
+fn read_retry<R: Read>(in_f: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
     loop {
-        let n = match in_f.read(&mut buf) {
+        match in_f.read(buf) {
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            r => return r,
+        }
+    }
+}
+
+/// feed whole content of in_f to d, inputfile is used for error reporting
+pub fn update_digest<D, R>(d: &mut D, in_f: &mut R, inputfile: &str) -> Result<(), ApplicationError> where D: digest::Update, R: Read {
+    update_digest_with_buffer(d, in_f, inputfile, DEFAULT_BUFFER_SIZE)
+}
+
+/// same as update_digest, with read buffer of buffer_size bytes
+pub fn update_digest_with_buffer<D, R>(
+    d: &mut D,
+    in_f: &mut R,
+    inputfile: &str,
+    buffer_size: usize,
+) -> Result<(), ApplicationError>
+where
+    D: digest::Update,
+    R: Read,
+{
+    let mut buf: Vec<u8> = vec![0u8; buffer_size.max(1)];
+    loop {
+        // short read doesn't mean end of input(e.g. pipe), only 0 does
+        let n = match read_retry(in_f, &mut buf) {
             Ok(v) => Ok(v),
-            Err(e) => Err(ApplicationError::Io(e))
+            Err(e) => Err(ApplicationError::from_io(e, inputfile, "failed to read file"))
         }?;
-        d.update(&buf[0..n]);
-        if n == 0 || n < 1024 {
+        if n == 0 {
             break;
         }
+        d.update(&buf[0..n]);
     }
     Ok(())
 }
+
+fn hash_read_ahead<D>(
+    d: &mut D,
+    filled: std::sync::mpsc::Receiver<std::io::Result<(Vec<u8>, usize)>>,
+    empty: std::sync::mpsc::Sender<Vec<u8>>,
+    inputfile: &str,
+) -> Result<(), ApplicationError>
+where
+    D: digest::Update,
+{
+    // both channels are dropped on return, so that the reader thread stops even if hashing fails
+    loop {
+        match filled.recv() {
+            Ok(Ok((_, 0))) | Err(_) => return Ok(()),
+            Ok(Ok((buf, n))) => {
+                d.update(&buf[0..n]);
+                let _ = empty.send(buf);
+            }
+            Ok(Err(e)) => return Err(ApplicationError::from_io(e, inputfile, "failed to read file")),
+        }
+    }
+}
+
+/// same as update_digest_with_buffer, but reading is done in another thread to overlap I/O with hashing
+pub fn update_digest_read_ahead<D, R>(
+    d: &mut D,
+    in_f: &mut R,
+    inputfile: &str,
+    buffer_size: usize,
+) -> Result<(), ApplicationError>
+where
+    D: digest::Update,
+    R: Read + Send,
+{
+    let (filled_tx, filled_rx) = std::sync::mpsc::sync_channel(READ_AHEAD_BUFFERS);
+    let (empty_tx, empty_rx) = std::sync::mpsc::channel::<Vec<u8>>();
+    for _ in 0..READ_AHEAD_BUFFERS {
+        let _ = empty_tx.send(vec![0u8; buffer_size.max(1)]);
+    }
+    std::thread::scope(|scope| {
+        scope.spawn(move || {
+            while let Ok(mut buf) = empty_rx.recv() {
+                let ret = read_retry(in_f, &mut buf);
+                let done = !matches!(ret, Ok(n) if n != 0);
+                if filled_tx.send(ret.map(|n| (buf, n))).is_err() || done {
+                    break;
+                }
+            }
+        });
+        hash_read_ahead(d, filled_rx, empty_tx, inputfile)
+    })
+}
+
+/// hash regular file through memory map, returns false if the file cannot be mapped
+fn update_digest_mmap<D>(d: &mut D, in_f: &std::fs::File) -> bool
+where
+    D: digest::Update,
+{
+    match in_f.metadata() {
+        // empty file cannot be mapped, and special files may change their size while reading
+        Ok(m) if m.is_file() && m.len() != 0 => (),
+        _ => return false,
+    };
+    // the file must not be truncated while mapped, it is same restriction as other hash tools using mmap
+    let mapped = match unsafe { memmap2::Mmap::map(in_f) } {
+        Ok(v) => v,
+        Err(_) => return false,
+    };
+    #[cfg(unix)]
+    let _ = mapped.advise(memmap2::Advice::Sequential);
+    d.update(&mapped);
+    true
+}
+
+/// feed whole content of the file to d, '-' means stdin
+pub fn update_digest_from_path<D>(d: &mut D, inputfile: &str, options: &ReadOptions) -> Result<(), ApplicationError>
+where
+    D: digest::Update,
+{
+    if inputfile == "-" {
+        let mut in_f = std::io::stdin();
+        if options.read_ahead {
+            return update_digest_read_ahead(d, &mut in_f, inputfile, options.buffer_size);
+        }
+        return update_digest_with_buffer(d, &mut in_f, inputfile, options.buffer_size);
+    }
+    let mut in_f = match std::fs::File::open(inputfile) {
+        Ok(v) => v,
+        Err(e) => return Err(ApplicationError::from_io(e, inputfile, "failed to open file for read")),
+    };
+    if options.mmap && update_digest_mmap(d, &in_f) {
+        return Ok(());
+    }
+    if options.read_ahead {
+        update_digest_read_ahead(d, &mut in_f, inputfile, options.buffer_size)
+    } else {
+        update_digest_with_buffer(d, &mut in_f, inputfile, options.buffer_size)
+    }
+}
+
+pub fn to_hex_string(data: &[u8]) -> String {
+    let mut ret = String::new();
+    for b in data {
+        ret.push_str(format!("{:02x}", b).as_str());
+    }
+    ret
+}
+
+pub fn from_hex_string(data: &str) -> Option<Vec<u8>> {
+    if !data.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..data.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(data.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// lowercase hex
+    Hex,
+    /// uppercase hex
+    UpperHex,
+    /// RFC4648 base64 with padding
+    Base64,
+    /// RFC4648 URL safe base64 without padding
+    Base64Url,
+    /// RFC4648 base32 with padding
+    Base32,
+    /// base32 used by Nix, which has its own alphabet and bit order
+    Nix32,
+}
+
+pub const ENCODING_NAMES: &[&str] = &["hex", "HEX", "base64", "base64url", "base32", "nix32"];
+
+/// order of trying to decode digest, hex is preferred because it is the default
+const DECODE_ORDER: &[Encoding] = &[
+    Encoding::Hex,
+    Encoding::Base64,
+    Encoding::Base64Url,
+    Encoding::Base32,
+    Encoding::Nix32,
+];
+
+const NIX32_CHARS: &[u8] = b"0123456789abcdfghijklmnpqrsvwxyz";
+
+fn to_nix32_string(data: &[u8]) -> String {
+    if data.is_empty() {
+        return String::new();
+    }
+    let len = (data.len() * 8 - 1) / 5 + 1;
+    let mut ret = String::new();
+    for n in (0..len).rev() {
+        let b = n * 5;
+        let (i, j) = (b / 8, b % 8);
+        let mut c = data[i] >> j;
+        if i + 1 < data.len() && j > 3 {
+            c |= data[i + 1] << (8 - j);
+        }
+        ret.push(NIX32_CHARS[(c & 0x1f) as usize] as char);
+    }
+    ret
+}
+
+fn from_nix32_string(data: &str) -> Option<Vec<u8>> {
+    let len = data.len() * 5 / 8;
+    let mut ret = vec![0u8; len];
+    for (n, c) in data.bytes().rev().enumerate() {
+        let digit = NIX32_CHARS.iter().position(|v| *v == c)? as u16;
+        let b = n * 5;
+        let (i, j) = (b / 8, b % 8);
+        let v = digit << j;
+        if i < len {
+            ret[i] |= v as u8;
+        } else if v != 0 {
+            return None;
+        }
+        let carry = (v >> 8) as u8;
+        if i + 1 < len {
+            ret[i + 1] |= carry;
+        } else if carry != 0 {
+            return None;
+        }
+    }
+    Some(ret)
+}
+
+impl Encoding {
+    pub fn from_name(name: &str) -> Result<Encoding, ApplicationError> {
+        match name {
+            "hex" => Ok(Encoding::Hex),
+            "HEX" => Ok(Encoding::UpperHex),
+            "base64" => Ok(Encoding::Base64),
+            "base64url" => Ok(Encoding::Base64Url),
+            "base32" => Ok(Encoding::Base32),
+            "nix32" => Ok(Encoding::Nix32),
+            _ => Err(ApplicationError::from_parameter(
+                "encoding",
+                format!("unknown encoding({})", name).as_str(),
+            )),
+        }
+    }
+    pub fn encode(self, data: &[u8]) -> String {
+        match self {
+            Encoding::Hex => to_hex_string(data),
+            Encoding::UpperHex => data_encoding::HEXUPPER.encode(data),
+            Encoding::Base64 => data_encoding::BASE64.encode(data),
+            Encoding::Base64Url => data_encoding::BASE64URL_NOPAD.encode(data),
+            Encoding::Base32 => data_encoding::BASE32.encode(data),
+            Encoding::Nix32 => to_nix32_string(data),
+        }
+    }
+    /// decode digest, padding is optional and hex and base32 are case insensitive
+    pub fn decode(self, data: &str) -> Option<Vec<u8>> {
+        let bytes = data.as_bytes();
+        match self {
+            Encoding::Hex | Encoding::UpperHex => data_encoding::HEXLOWER_PERMISSIVE.decode(bytes).ok(),
+            Encoding::Base64 => data_encoding::BASE64
+                .decode(bytes)
+                .or_else(|_| data_encoding::BASE64_NOPAD.decode(bytes))
+                .ok(),
+            Encoding::Base64Url => data_encoding::BASE64URL_NOPAD
+                .decode(bytes)
+                .or_else(|_| data_encoding::BASE64URL.decode(bytes))
+                .ok(),
+            Encoding::Base32 => {
+                let upper = data.to_uppercase();
+                data_encoding::BASE32
+                    .decode(upper.as_bytes())
+                    .or_else(|_| data_encoding::BASE32_NOPAD.decode(upper.as_bytes()))
+                    .ok()
+            }
+            Encoding::Nix32 => from_nix32_string(data),
+        }
+    }
+}
+
+/// algorithm prefixes of SRI(`sha384-...`) and Nix(`sha256:...`, `sha256-...`) digests, and names in ALGORITHM_NAMES
+const DIGEST_PREFIXES: &[(&str, &str)] = &[
+    ("md5", "md5"),
+    ("sha1", "sha1"),
+    ("sha224", "sha2-224"),
+    ("sha256", "sha2-256"),
+    ("sha384", "sha2-384"),
+    ("sha512", "sha2-512"),
+    ("sha3-224", "sha3-224"),
+    ("sha3-256", "sha3-256"),
+    ("sha3-384", "sha3-384"),
+    ("sha3-512", "sha3-512"),
+    ("blake2b", "blake2b"),
+    ("blake2s", "blake2s"),
+    ("blake3", "blake3"),
+];
+
+/// split recognised `<algo>-` or `<algo>:` prefix from digest, algorithm is returned as the name in ALGORITHM_NAMES
+pub fn split_digest_prefix(data: &str) -> (Option<&'static str>, &str) {
+    for (prefix, name) in DIGEST_PREFIXES {
+        if let Some(rest) = data.strip_prefix(prefix).and_then(|v| v.strip_prefix(['-', ':'])) {
+            return (Some(name), rest);
+        }
+    }
+    (None, data)
+}
+
+/// false if the digest has algorithm prefix and it is not the algorithm(name in ALGORITHM_NAMES)
+pub fn digest_prefix_matches(data: &str, algorithm: &str) -> bool {
+    match split_digest_prefix(data) {
+        (Some(name), _) => name == algorithm,
+        (None, _) => true,
+    }
+}
+
+/// decode digest in every encoding which can decode it, preferred encoding first.
+/// algorithm prefix like `sha256-` is ignored, use digest_prefix_matches to verify it
+pub fn decode_digest(data: &str) -> Vec<(Encoding, Vec<u8>)> {
+    let (_, data) = split_digest_prefix(data);
+    DECODE_ORDER
+        .iter()
+        .filter_map(|e| e.decode(data).map(|v| (*e, v)))
+        .collect()
+}
+
+/// length in bytes of the digest decoded by the preferred encoding
+pub fn guess_digest_length(data: &str) -> Option<usize> {
+    decode_digest(data).first().map(|(_, v)| v.len())
+}
+
+/// encode actual digest in the same encoding as expected one, to show both in the same way
+pub fn encode_as_expected(expected: &[(Encoding, Vec<u8>)], actual: &[u8]) -> String {
+    let encoding = expected
+        .iter()
+        .find(|(_, v)| v.len() == actual.len())
+        .map(|(e, _)| *e)
+        .unwrap_or(Encoding::Hex);
+    encoding.encode(actual)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    #[test]
+    fn nix32_round_trip() {
+        let digest = from_hex_string(EMPTY_SHA256).unwrap();
+        // same as `nix hash to-base32`
+        let nix32 = "0mdqa9w1p6cmli6976v4wi0sw9r4p5prkj7lzfd1877wk11c9c73";
+        assert_eq!(Encoding::Nix32.encode(&digest), nix32);
+        assert_eq!(Encoding::Nix32.decode(nix32).unwrap(), digest);
+        for len in 0..70 {
+            let data: Vec<u8> = (0..len).map(|i| (i * 37 + 11) as u8).collect();
+            assert_eq!(Encoding::Nix32.decode(&Encoding::Nix32.encode(&data)).unwrap(), data);
+        }
+        // 'e', 'o', 't' and 'u' are not in nix32 alphabet
+        assert!(Encoding::Nix32.decode("e").is_none());
+    }
+
+    fn sha256_of_file(path: &str, options: &ReadOptions) -> Vec<u8> {
+        let mut d = sha2::Sha256::default();
+        update_digest_from_path(&mut d, path, options).unwrap();
+        digest::Digest::finalize(d).to_vec()
+    }
+
+    #[test]
+    fn read_paths_produce_same_digest() {
+        let dir = std::env::temp_dir().join(format!("hast-test-read-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // empty, smaller than a buffer, and several buffers with a partial last one
+        for len in &[0, 100, 3 * DEFAULT_BUFFER_SIZE + 17] {
+            let data: Vec<u8> = (0..*len).map(|i| (i % 251) as u8).collect();
+            let path = dir.join(format!("{}.bin", len));
+            std::fs::write(&path, &data).unwrap();
+            let path = path.to_str().unwrap();
+            let mut d = sha2::Sha256::default();
+            digest::Update::update(&mut d, &data);
+            let expected = digest::Digest::finalize(d).to_vec();
+            for buffer_size in &[1, 7, DEFAULT_BUFFER_SIZE] {
+                for (mmap, read_ahead) in &[(false, false), (false, true), (true, false), (true, true)] {
+                    let options = ReadOptions {
+                        buffer_size: *buffer_size,
+                        mmap: *mmap,
+                        read_ahead: *read_ahead,
+                    };
+                    assert_eq!(sha256_of_file(path, &options), expected, "{} {:?}", len, options);
+                }
+            }
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// returns some bytes, and then fails
+    struct FailingReader(usize);
+
+    impl Read for FailingReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.0 == 0 {
+                return Err(std::io::Error::other("broken"));
+            }
+            let n = self.0.min(buf.len());
+            self.0 -= n;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn read_ahead_propagates_error() {
+        for buffer_size in &[1, 7, DEFAULT_BUFFER_SIZE] {
+            let mut d = sha2::Sha256::default();
+            let mut in_f = FailingReader(100);
+            // returns instead of waiting for the reader thread forever
+            let e = update_digest_read_ahead(&mut d, &mut in_f, "failing", *buffer_size).unwrap_err();
+            assert_eq!(e.kind(), "io");
+            let mut in_f = FailingReader(100);
+            assert!(update_digest_with_buffer(&mut d, &mut in_f, "failing", *buffer_size).is_err());
+        }
+    }
+
+    #[test]
+    fn mmap_falls_back_for_empty_file() {
+        let path = std::env::temp_dir().join(format!("hast-test-mmap-{}", std::process::id()));
+        std::fs::write(&path, b"").unwrap();
+        let mut d = sha2::Sha256::default();
+        let in_f = std::fs::File::open(&path).unwrap();
+        assert!(!update_digest_mmap(&mut d, &in_f));
+        std::fs::write(&path, b"a").unwrap();
+        let in_f = std::fs::File::open(&path).unwrap();
+        assert!(update_digest_mmap(&mut d, &in_f));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn decoders_are_permissive() {
+        let digest = from_hex_string(EMPTY_SHA256).unwrap();
+        assert_eq!(Encoding::Hex.decode(&EMPTY_SHA256.to_uppercase()).unwrap(), digest);
+        let base64 = Encoding::Base64.encode(&digest);
+        assert_eq!(Encoding::Base64.decode(&base64).unwrap(), digest);
+        assert_eq!(Encoding::Base64.decode(base64.trim_end_matches('=')).unwrap(), digest);
+        let base64url = Encoding::Base64Url.encode(&digest);
+        assert!(!base64url.ends_with('='));
+        assert_eq!(Encoding::Base64Url.decode(&format!("{}=", base64url)).unwrap(), digest);
+        let base32 = Encoding::Base32.encode(&digest);
+        assert_eq!(Encoding::Base32.decode(&base32.to_lowercase()).unwrap(), digest);
+        assert_eq!(Encoding::Base32.decode(base32.trim_end_matches('=')).unwrap(), digest);
+    }
+
+    #[test]
+    fn decode_digest_accepts_sri_and_nix_prefix() {
+        let digest = from_hex_string(EMPTY_SHA256).unwrap();
+        let sri = format!("sha256-{}", Encoding::Base64.encode(&digest));
+        let nix = format!("sha256:{}", Encoding::Nix32.encode(&digest));
+        for v in [sri.as_str(), nix.as_str(), EMPTY_SHA256] {
+            assert!(decode_digest(v).iter().any(|(_, d)| *d == digest), "{}", v);
+            assert!(digest_prefix_matches(v, "sha2-256"));
+        }
+        assert_eq!(split_digest_prefix(&sri).0, Some("sha2-256"));
+        assert!(!digest_prefix_matches(&sri, "sha2-512"));
+        assert_eq!(split_digest_prefix("sha3-256-abc"), (Some("sha3-256"), "abc"));
+        assert_eq!(split_digest_prefix(EMPTY_SHA256), (None, EMPTY_SHA256));
+    }
+}
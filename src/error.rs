@@ -1,4 +1,33 @@
-use std::io::Error as IoError;
+use std::path::{Path, PathBuf};
+
+// process exit codes, these values must be kept stable because scripts depend on them
+/// hash mismatch, or extra file found by check
+pub const EXIT_MISMATCH: i32 = 1;
+/// failed to read or write file
+pub const EXIT_IO: i32 = 2;
+/// file not found
+pub const EXIT_MISSING: i32 = 3;
+/// invalid checksum file
+pub const EXIT_MANIFEST: i32 = 4;
+/// invalid command line arguments
+pub const EXIT_PARAMETER: i32 = 5;
+
+#[derive(Debug)]
+pub struct IoError {
+    e: std::io::Error,
+    path: PathBuf,
+    message: String,
+}
+
+impl IoError {
+    pub fn kind(&self) -> std::io::ErrorKind {
+        self.e.kind()
+    }
+    /// file which caused the error, "-" means stdin or stdout
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
 
 #[derive(Debug)]
 pub struct InvalidParameter {
@@ -6,6 +35,12 @@ pub struct InvalidParameter {
     message: String
 }
 
+impl InvalidParameter {
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
 #[derive(Debug)]
 pub struct CheckError {
     message: String,
@@ -23,14 +58,35 @@ pub struct ParseError {
 
 impl std::fmt::Display for CheckError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}(file1 = ({}, {}), file2 = ({}, {})", self.message, self.filename1, self.hash1, self.filename2, self.hash2)
+        if self.filename1 == self.filename2 {
+            write!(f, "{}({}): expected {}, but {}", self.message, self.filename1, self.hash1, self.hash2)
+        } else {
+            write!(
+                f,
+                "{}({}, {}): expected {}, but {}",
+                self.message, self.filename1, self.filename2, self.hash1, self.hash2
+            )
+        }
+    }
+}
+
+impl CheckError {
+    pub fn actual(&self) -> &str {
+        &self.hash2
     }
 }
 
 #[derive(Debug)]
 pub struct CsvError {
     message: String,
-    kind: csv::ErrorKind
+    e: csv::Error
+}
+
+impl CsvError {
+    /// position of the record which caused the error, if known
+    pub fn position(&self) -> Option<&csv::Position> {
+        self.e.position()
+    }
 }
 
 #[derive(Debug)]
@@ -43,27 +99,122 @@ pub struct GlobError {
     e: glob::GlobError,
     message: String,
 }
+
+impl GlobError {
+    pub fn path(&self) -> &Path {
+        self.e.path()
+    }
+}
 #[derive(Debug)]
 pub struct PathError {
-    p: std::path::PathBuf,
+    p: PathBuf,
+    message: String,
+}
+#[derive(Debug)]
+pub struct WalkError {
+    e: ignore::Error,
+    message: String,
+}
+#[derive(Debug)]
+pub struct ManifestError {
+    filename: String,
+    line: usize,
     message: String,
 }
 #[derive(Debug)]
+pub struct CheckSummaryError {
+    ok: usize,
+    failed: usize,
+    missing: usize,
+    unreadable: usize,
+    extra: usize,
+}
+#[derive(Debug)]
 pub enum ApplicationError {
-    Io(std::io::Error),
+    Io(IoError),
     Parameter(InvalidParameter),
-    Clap(clap::Error),
     Check(CheckError),
     Csv(CsvError),
     Parse(ParseError),
     GlobPattern(GlobPatternError),
     Glob(GlobError),
-    Path(PathError)
+    Path(PathError),
+    Walk(WalkError),
+    Manifest(ManifestError),
+    CheckSummary(CheckSummaryError)
 }
 
 impl ApplicationError {
-    pub fn from_io(e: &IoError, msg: &str) -> ApplicationError {
-        ApplicationError::Io(IoError::new(e.kind(), format!("{}: {}", msg, e.to_string())))
+    /// process exit code for the failure class, see EXIT_* constants
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ApplicationError::Check(_) => EXIT_MISMATCH,
+            ApplicationError::CheckSummary(e) => {
+                if e.failed + e.extra != 0 {
+                    EXIT_MISMATCH
+                } else if e.missing != 0 {
+                    EXIT_MISSING
+                } else {
+                    EXIT_IO
+                }
+            }
+            ApplicationError::Io(e) if e.kind() == std::io::ErrorKind::NotFound => EXIT_MISSING,
+            ApplicationError::Io(_) | ApplicationError::Glob(_) | ApplicationError::Path(_) | ApplicationError::Walk(_) => {
+                EXIT_IO
+            }
+            ApplicationError::Csv(e) if e.e.is_io_error() => EXIT_IO,
+            ApplicationError::Csv(_) | ApplicationError::Manifest(_) => EXIT_MANIFEST,
+            ApplicationError::Parameter(_)
+            | ApplicationError::Parse(_)
+            | ApplicationError::GlobPattern(_) => EXIT_PARAMETER,
+        }
+    }
+    /// file related to the error, if any
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            ApplicationError::Io(e) => Some(e.path()),
+            ApplicationError::Glob(e) => Some(e.path()),
+            ApplicationError::Path(e) => Some(&e.p),
+            _ => None,
+        }
+    }
+    /// short name of the error variant, used in json output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ApplicationError::Io(_) => "io",
+            ApplicationError::Parameter(_) => "parameter",
+            ApplicationError::Check(_) => "check",
+            ApplicationError::Csv(_) => "csv",
+            ApplicationError::Parse(_) => "parse",
+            ApplicationError::GlobPattern(_) => "glob_pattern",
+            ApplicationError::Glob(_) => "glob",
+            ApplicationError::Path(_) => "path",
+            ApplicationError::Walk(_) => "walk",
+            ApplicationError::Manifest(_) => "manifest",
+            ApplicationError::CheckSummary(_) => "check_summary",
+        }
+    }
+    /// message followed by its causes, Display shows only this error and the causes are given by source()
+    pub fn full_message(&self) -> String {
+        let mut ret = self.to_string();
+        let mut source = std::error::Error::source(self);
+        while let Some(e) = source {
+            let msg = e.to_string();
+            // some errors(e.g. glob::GlobError) also show their cause in Display
+            if !ret.contains(&msg) {
+                ret.push_str(": ");
+                ret.push_str(&msg);
+            }
+            source = e.source();
+        }
+        ret
+    }
+    pub fn from_io(e: std::io::Error, path: &str, msg: &str) -> ApplicationError {
+        ApplicationError::Io(IoError {
+            e,
+            path: PathBuf::from(path),
+            message: msg.to_owned()
+        })
     }
     pub fn from_parameter(name: &str, msg: &str) -> ApplicationError {
         ApplicationError::Parameter(InvalidParameter {
@@ -83,7 +234,7 @@ impl ApplicationError {
     pub fn from_csv(e: csv::Error, msg: &str) -> ApplicationError {
         ApplicationError::Csv(CsvError {
             message: msg.to_owned(),
-            kind: e.into_kind()
+            e
         })
     }
     pub fn from_parse_error(parameter: &str, msg: &str) -> ApplicationError {
@@ -94,21 +245,159 @@ impl ApplicationError {
     }
     pub fn from_glob_pattern_error(e: glob::PatternError, msg: &str) -> ApplicationError {
         ApplicationError::GlobPattern(GlobPatternError {
-            e: e,
+            e,
             message: msg.to_owned()
         })
     }
     pub fn from_glob_error(e: glob::GlobError, msg: &str) -> ApplicationError {
         ApplicationError::Glob(GlobError {
-            e: e,
+            e,
             message: msg.to_owned()
         })
     }
-    pub fn from_path_error(p: &std::path::Path, msg: &str) -> ApplicationError {
+    pub fn from_path_error(p: &Path, msg: &str) -> ApplicationError {
         ApplicationError::Path(PathError {
-            p: std::path::PathBuf::from(p),
+            p: PathBuf::from(p),
             message: msg.to_string()
         })
     }
+    pub fn from_walk(e: ignore::Error, msg: &str) -> ApplicationError {
+        ApplicationError::Walk(WalkError {
+            e,
+            message: msg.to_owned()
+        })
+    }
+    pub fn from_manifest(filename: &str, line: usize, msg: &str) -> ApplicationError {
+        ApplicationError::Manifest(ManifestError {
+            filename: filename.to_owned(),
+            line,
+            message: msg.to_owned()
+        })
+    }
+    pub fn from_check_summary(ok: usize, failed: usize, missing: usize, unreadable: usize, extra: usize) -> ApplicationError {
+        ApplicationError::CheckSummary(CheckSummaryError {
+            ok,
+            failed,
+            missing,
+            unreadable,
+            extra
+        })
+    }
+}
+impl std::fmt::Display for IoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({})", self.message, self.path.display())
+    }
 }
 
+impl std::fmt::Display for InvalidParameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid parameter({}): {}", self.name, self.message)
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({})", self.message, self.parameter)
+    }
+}
+
+impl std::fmt::Display for CsvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::fmt::Display for GlobPatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::fmt::Display for GlobError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::fmt::Display for PathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({})", self.message, self.p.display())
+    }
+}
+
+impl std::fmt::Display for WalkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 0 means the error is not related to a specific line
+        if self.line == 0 {
+            write!(f, "{}: {}", self.filename, self.message)
+        } else {
+            write!(f, "{}:{}: {}", self.filename, self.line, self.message)
+        }
+    }
+}
+
+impl std::fmt::Display for CheckSummaryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ok, {} mismatched, {} missing, {} unreadable, {} extra",
+            self.ok, self.failed, self.missing, self.unreadable, self.extra
+        )
+    }
+}
+
+impl std::fmt::Display for ApplicationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApplicationError::Io(e) => write!(f, "{}", e),
+            ApplicationError::Parameter(e) => write!(f, "{}", e),
+            ApplicationError::Check(e) => write!(f, "{}", e),
+            ApplicationError::Csv(e) => write!(f, "{}", e),
+            ApplicationError::Parse(e) => write!(f, "{}", e),
+            ApplicationError::GlobPattern(e) => write!(f, "{}", e),
+            ApplicationError::Glob(e) => write!(f, "{}", e),
+            ApplicationError::Path(e) => write!(f, "{}", e),
+            ApplicationError::Walk(e) => write!(f, "{}", e),
+            ApplicationError::Manifest(e) => write!(f, "{}", e),
+            ApplicationError::CheckSummary(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ApplicationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ApplicationError::Io(e) => Some(&e.e),
+            ApplicationError::Csv(e) => Some(&e.e),
+            ApplicationError::GlobPattern(e) => Some(&e.e),
+            ApplicationError::Glob(e) => Some(&e.e),
+            ApplicationError::Walk(e) => Some(&e.e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn cause_is_shown_once() {
+        let e = ApplicationError::from_io(
+            std::io::Error::new(std::io::ErrorKind::NotFound, "no such file"),
+            "a.txt",
+            "failed to open file for read",
+        );
+        assert_eq!(e.to_string(), "failed to open file for read(a.txt)");
+        assert_eq!(e.source().unwrap().to_string(), "no such file");
+        assert_eq!(e.full_message(), "failed to open file for read(a.txt): no such file");
+    }
+}
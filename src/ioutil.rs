@@ -5,7 +5,7 @@ pub fn get_file_or_stdin(filepath: &str) -> Result<Box<dyn Read>, ApplicationErr
     if filepath != "-" {
         match std::fs::File::open(filepath) {
             Ok(v) => Ok(Box::new(v)),
-            Err(e) => Err(ApplicationError::from_io(&e, format!("failed to open file for read({})", filepath).as_str()))
+            Err(e) => Err(ApplicationError::from_io(e, filepath, "failed to open file for read"))
         }
     } else {
         Ok(Box::new(std::io::stdin()))
@@ -16,10 +16,19 @@ pub fn create_file_for_write(path: &str) -> Result<Box<dyn Write>, ApplicationEr
     if path != "-" {
         match std::fs::File::create(path) {
             Ok(v) => Ok(Box::new(v)),
-            Err(e) => Err(ApplicationError::from_io(&e, format!("failed to create file for write({})", path).as_str()))
+            Err(e) => Err(ApplicationError::from_io(e, path, "failed to create file for write"))
         }
     } else {
         Ok(Box::new(std::io::stdout()))
     }
 }
 
+/// read whole content of key file, '-' means stdin
+pub fn read_key_file(path: &str) -> Result<Vec<u8>, ApplicationError> {
+    let mut in_f = get_file_or_stdin(path)?;
+    let mut ret = Vec::new();
+    match in_f.read_to_end(&mut ret) {
+        Ok(_) => Ok(ret),
+        Err(e) => Err(ApplicationError::from_io(e, path, "failed to read key file"))
+    }
+}
@@ -0,0 +1,35 @@
+//! hashing, manifest reading/writing and verification used by the `hast` command.
+//!
+//! ```no_run
+//! use hast::algorithm::Algorithm;
+//! use hast::digestutil;
+//!
+//! let algorithm = Algorithm::from_name("sha2-256", 0).unwrap();
+//! let mut hasher = algorithm.create_hasher().unwrap();
+//! let mut f = std::fs::File::open("Cargo.toml").unwrap();
+//! digestutil::update_digest(&mut hasher, &mut f, "Cargo.toml").unwrap();
+//! println!("{}", digestutil::to_hex_string(&hasher.finalize_reset()));
+//! ```
+extern crate blake2;
+extern crate blake3;
+extern crate csv;
+extern crate digest;
+extern crate glob;
+extern crate ignore;
+extern crate sha1;
+extern crate sha2;
+extern crate sha3;
+
+pub mod algorithm;
+#[cfg(feature = "async")]
+pub mod asyncutil;
+pub mod digestutil;
+pub mod error;
+pub mod ioutil;
+pub mod manifest;
+pub mod parallel;
+pub mod walk;
+
+pub use algorithm::{Algorithm, Hasher, ALGORITHM_NAMES};
+pub use error::ApplicationError;
+pub use manifest::{Manifest, ManifestEntry, ManifestFormat, ManifestHeader, ManifestWriter};
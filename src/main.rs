@@ -1,21 +1,18 @@
-extern crate blake2;
 extern crate clap;
-extern crate csv;
-extern crate digest;
-extern crate sha1;
-extern crate sha2;
-extern crate sha3;
 extern crate glob;
+extern crate hast;
 
-use clap::App;
+use clap::{App, ArgMatches};
 use clap::SubCommand;
 
 mod calc;
 mod check;
-mod digestutil;
-mod error;
-mod ioutil;
 mod command;
+mod sri;
+mod tee;
+mod verify;
+
+use hast::{algorithm, digestutil, error, ioutil, manifest, parallel, walk};
 
 use error::ApplicationError;
 
@@ -40,24 +37,60 @@ fn create_app<'a, 'b>() -> App<'a, 'b> {
                 .subcommand(command::create_calc_sha2())
                 .subcommand(command::create_calc_sha3())
                 .subcommand(command::create_calc_shake())
-                .subcommand(command::create_calc_blake2()),
+                .subcommand(command::create_calc_blake2())
+                .subcommand(command::create_calc_blake3())
+                .subcommand(command::create_calc_crc())
+                .subcommand(command::create_calc_adler32())
+                .subcommand(command::create_calc_xxhash())
+                .subcommand(command::create_calc_multi()),
         )
         .subcommand(
             SubCommand::with_name("check")
                 .about("check hash")
+                .subcommand(command::create_check_auto())
                 .subcommand(command::create_check_md5())
                 .subcommand(command::create_check_sha1())
                 .subcommand(command::create_check_sha2())
                 .subcommand(command::create_check_sha3())
                 .subcommand(command::create_check_shake())
-                .subcommand(command::create_check_blake2()),
+                .subcommand(command::create_check_blake2())
+                .subcommand(command::create_check_blake3())
+                .subcommand(command::create_check_crc())
+                .subcommand(command::create_check_adler32())
+                .subcommand(command::create_check_xxhash()),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("verify a file with expected hash")
+                .subcommand(command::create_verify_md5())
+                .subcommand(command::create_verify_sha1())
+                .subcommand(command::create_verify_sha2())
+                .subcommand(command::create_verify_sha3())
+                .subcommand(command::create_verify_shake())
+                .subcommand(command::create_verify_blake2())
+                .subcommand(command::create_verify_blake3())
+                .subcommand(command::create_verify_crc())
+                .subcommand(command::create_verify_adler32())
+                .subcommand(command::create_verify_xxhash()),
+        )
+        .subcommand(command::create_sri())
+        .subcommand(
+            SubCommand::with_name("tee")
+                .about("copy stdin to stdout and record hash of the data")
+                .subcommand(command::create_tee_md5())
+                .subcommand(command::create_tee_sha1())
+                .subcommand(command::create_tee_sha2())
+                .subcommand(command::create_tee_sha3())
+                .subcommand(command::create_tee_shake())
+                .subcommand(command::create_tee_blake2())
+                .subcommand(command::create_tee_blake3())
+                .subcommand(command::create_tee_crc())
+                .subcommand(command::create_tee_adler32())
+                .subcommand(command::create_tee_xxhash()),
         )
 }
 
-fn main() -> Result<(), ApplicationError> {
-    let app = create_app();
-    let mut app2 = app.clone();
-    let matches = app.get_matches();
+fn run(matches: &ArgMatches, app2: &mut App) -> Result<(), ApplicationError> {
     match matches.subcommand() {
         ("calc", Some(app)) => match app.subcommand() {
             ("md5", Some(app)) => calc::do_calc_md5(app),
@@ -66,6 +99,11 @@ fn main() -> Result<(), ApplicationError> {
             ("sha3", Some(app)) => calc::do_calc_sha3(app),
             ("shake", Some(app)) => calc::do_calc_shake(app),
             ("blake2", Some(app)) => calc::do_calc_blake2(app),
+            ("blake3", Some(app)) => calc::do_calc_blake3(app),
+            ("crc", Some(app)) => calc::do_calc_crc(app),
+            ("adler32", Some(app)) => calc::do_calc_adler32(app),
+            ("xxhash", Some(app)) => calc::do_calc_xxhash(app),
+            ("multi", Some(app)) => calc::do_calc_multi(app),
             _ => {
                 return Err(ApplicationError::from_parameter(
                     "unknown",
@@ -74,12 +112,54 @@ fn main() -> Result<(), ApplicationError> {
             }
         },
         ("check", Some(app)) => match app.subcommand() {
+            ("auto", Some(app)) => check::do_check_auto(app),
             ("md5", Some(app)) => check::do_check_md5(app),
             ("sha1", Some(app)) => check::do_check_sha1(app),
             ("sha2", Some(app)) => check::do_check_sha2(app),
             ("sha3", Some(app)) => check::do_check_sha3(app),
             ("shake", Some(app)) => check::do_check_shake(app),
             ("blake2", Some(app)) => check::do_check_blake2(app),
+            ("blake3", Some(app)) => check::do_check_blake3(app),
+            ("crc", Some(app)) => check::do_check_crc(app),
+            ("adler32", Some(app)) => check::do_check_adler32(app),
+            ("xxhash", Some(app)) => check::do_check_xxhash(app),
+            _ => {
+                return Err(ApplicationError::from_parameter(
+                    "unknown",
+                    "unknown command",
+                ))
+            }
+        },
+        ("verify", Some(app)) => match app.subcommand() {
+            ("md5", Some(app)) => verify::do_verify_md5(app),
+            ("sha1", Some(app)) => verify::do_verify_sha1(app),
+            ("sha2", Some(app)) => verify::do_verify_sha2(app),
+            ("sha3", Some(app)) => verify::do_verify_sha3(app),
+            ("shake", Some(app)) => verify::do_verify_shake(app),
+            ("blake2", Some(app)) => verify::do_verify_blake2(app),
+            ("blake3", Some(app)) => verify::do_verify_blake3(app),
+            ("crc", Some(app)) => verify::do_verify_crc(app),
+            ("adler32", Some(app)) => verify::do_verify_adler32(app),
+            ("xxhash", Some(app)) => verify::do_verify_xxhash(app),
+            _ => {
+                return Err(ApplicationError::from_parameter(
+                    "unknown",
+                    "unknown command",
+                ))
+            }
+        },
+        ("sri", Some(app)) => sri::do_sri(app),
+        ("tee", Some(app)) => match app.subcommand() {
+            ("md5", Some(app)) => tee::do_tee_md5(app),
+            ("sha1", Some(app)) => tee::do_tee_sha1(app),
+            ("sha2", Some(app)) => tee::do_tee_sha2(app),
+            ("sha3", Some(app)) => tee::do_tee_sha3(app),
+            ("shake", Some(app)) => tee::do_tee_shake(app),
+            ("blake2", Some(app)) => tee::do_tee_blake2(app),
+            ("blake3", Some(app)) => tee::do_tee_blake3(app),
+            ("crc", Some(app)) => tee::do_tee_crc(app),
+            ("adler32", Some(app)) => tee::do_tee_adler32(app),
+            ("xxhash", Some(app)) => tee::do_tee_xxhash(app),
             _ => {
                 return Err(ApplicationError::from_parameter(
                     "unknown",
@@ -88,10 +168,14 @@ fn main() -> Result<(), ApplicationError> {
             }
         },
         _ => {
-            match app2.print_long_help() {
-                Err(e) => return Err(ApplicationError::Clap(e)),
-                _ => (),
-            };
+            if let Err(e) = app2.print_long_help() {
+                return Err(ApplicationError::from_io(
+                    std::io::Error::other(e.to_string()),
+                    "-",
+                    "failed to print help",
+                ));
+            }
+            println!();
             return Err(ApplicationError::from_parameter(
                 "unknown",
                 "unknown command",
@@ -100,3 +184,26 @@ fn main() -> Result<(), ApplicationError> {
     }?;
     Ok(())
 }
+
+fn main() {
+    let app = create_app();
+    let mut app2 = app.clone();
+    let matches = match app.get_matches_safe() {
+        Ok(v) => v,
+        // help and version are not errors
+        Err(e) if !e.use_stderr() => e.exit(),
+        // clap formats its own message with usage
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(error::EXIT_PARAMETER);
+        }
+    };
+    if let Err(e) = run(&matches, &mut app2) {
+        match &e {
+            // results are already reported by check
+            ApplicationError::CheckSummary(_) => {}
+            _ => eprintln!("Error: {}", e.full_message()),
+        }
+        std::process::exit(e.exit_code());
+    }
+}
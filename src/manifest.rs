@@ -0,0 +1,982 @@
+use super::algorithm::{self, Algorithm};
+use super::digestutil::{self, ReadOptions};
+use super::error::{ApplicationError, CheckError};
+use super::parallel;
+use super::walk::WalkOptions;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    /// `path,hash[,hash...]`
+    Csv,
+    /// GNU coreutils style `hash  path` or `hash *path`
+    Gnu,
+    /// BSD style `ALGORITHM (path) = hash`, algorithm is specified per line
+    Bsd,
+    /// array of objects, one object per file and algorithm
+    Json,
+    /// JSON Lines, one object per line
+    Jsonl,
+}
+
+pub const FORMAT_NAMES: &[&str] = &["csv", "gnu", "bsd", "json", "jsonl"];
+
+impl ManifestFormat {
+    pub fn from_name(name: &str) -> Result<ManifestFormat, ApplicationError> {
+        match name {
+            "csv" => Ok(ManifestFormat::Csv),
+            "gnu" => Ok(ManifestFormat::Gnu),
+            "bsd" => Ok(ManifestFormat::Bsd),
+            "json" => Ok(ManifestFormat::Json),
+            "jsonl" => Ok(ManifestFormat::Jsonl),
+            _ => Err(ApplicationError::from_parameter(
+                "format",
+                format!("unknown format({})", name).as_str(),
+            )),
+        }
+    }
+}
+
+/// first line which starts with this prefix is metadata header,
+/// like `#hast version=0.1.1 created=2020-01-01T00:00:00Z basepath=/path/to/dir algorithm=sha2-256`
+const HEADER_PREFIX: &str = "#hast ";
+
+/// metadata written at the top of manifest, so that check can configure itself
+#[derive(Debug, Clone)]
+pub struct ManifestHeader {
+    pub version: String,
+    /// creation time in UTC, RFC3339 format
+    pub created: String,
+    /// directory which relative paths in manifest are based on
+    pub basepath: String,
+    /// algorithms and parameters except keys, in order of hash columns
+    pub algorithms: Vec<Algorithm>,
+}
+
+fn unix_seconds(t: std::time::SystemTime) -> u64 {
+    t.duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// format unix time in RFC3339 UTC
+pub fn format_utc(secs: u64) -> String {
+    // days to civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = secs / 86400 + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    let rem = secs % 86400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// percent encoding for header values, which are separated by space
+fn encode_header_value(value: &str) -> String {
+    let mut ret = String::new();
+    for c in value.chars() {
+        match c {
+            '%' | ' ' | ',' | '=' | '\n' | '\r' => ret.push_str(format!("%{:02X}", c as u32).as_str()),
+            _ => ret.push(c),
+        }
+    }
+    ret
+}
+
+fn decode_header_value(value: &str) -> Option<String> {
+    let mut ret = Vec::new();
+    let bytes = value.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            ret.push(u8::from_str_radix(value.get(i + 1..i + 3)?, 16).ok()?);
+            i += 3;
+        } else {
+            ret.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(ret).ok()
+}
+
+impl ManifestHeader {
+    pub fn new(algorithms: &[Algorithm]) -> Result<ManifestHeader, ApplicationError> {
+        let basepath = match std::env::current_dir() {
+            Ok(v) => Ok(v),
+            Err(e) => Err(ApplicationError::from_io(e, ".", "failed to get current directory")),
+        }?;
+        let basepath = match basepath.to_str() {
+            Some(v) => Ok(v.to_owned()),
+            None => Err(ApplicationError::from_path_error(
+                &basepath,
+                "failed to extract path string",
+            )),
+        }?;
+        Ok(ManifestHeader {
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            created: format_utc(unix_seconds(std::time::SystemTime::now())),
+            basepath,
+            algorithms: algorithms.to_vec(),
+        })
+    }
+    fn to_line(&self) -> String {
+        let algorithms: Vec<String> = self.algorithms.iter().map(|a| encode_header_value(&a.to_spec())).collect();
+        format!(
+            "{}version={} created={} basepath={} algorithm={}\n",
+            HEADER_PREFIX,
+            encode_header_value(&self.version),
+            encode_header_value(&self.created),
+            encode_header_value(&self.basepath),
+            algorithms.join(",")
+        )
+    }
+    fn parse(line: &str) -> Option<ManifestHeader> {
+        let mut ret = ManifestHeader {
+            version: String::new(),
+            created: String::new(),
+            basepath: String::new(),
+            algorithms: Vec::new(),
+        };
+        for field in line.strip_prefix(HEADER_PREFIX)?.split(' ').filter(|v| !v.is_empty()) {
+            let (key, value) = field.split_once('=')?;
+            match key {
+                "version" => ret.version = decode_header_value(value)?,
+                "created" => ret.created = decode_header_value(value)?,
+                "basepath" => ret.basepath = decode_header_value(value)?,
+                "algorithm" => {
+                    ret.algorithms = value
+                        .split(',')
+                        .map(|v| Algorithm::from_spec(&decode_header_value(v)?))
+                        .collect::<Option<Vec<Algorithm>>>()?
+                }
+                // ignore fields added by newer version
+                _ => {}
+            }
+        }
+        Some(ret)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    pub header: Option<ManifestHeader>,
+    pub entries: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub hash: String,
+    /// algorithm specified in manifest line, if any
+    pub algorithm: Option<Algorithm>,
+}
+
+/// file attributes written in json formats, None for stdin
+#[derive(Debug, Clone, Default)]
+pub struct FileInfo {
+    pub size: Option<u64>,
+    /// modification time in unix seconds
+    pub mtime: Option<u64>,
+}
+
+impl FileInfo {
+    pub fn from_path(path: &str) -> FileInfo {
+        if path == "-" {
+            return FileInfo::default();
+        }
+        match std::fs::metadata(path) {
+            Ok(v) => FileInfo {
+                size: Some(v.len()),
+                mtime: v.modified().ok().map(unix_seconds),
+            },
+            Err(_) => FileInfo::default(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonEntry<'a> {
+    path: &'a str,
+    algorithm: String,
+    hash: &'a str,
+    size: Option<u64>,
+    mtime: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonManifestEntry {
+    path: String,
+    hash: String,
+    algorithm: Option<String>,
+}
+
+enum WriterInner<W: Write> {
+    Csv(Box<csv::Writer<W>>),
+    Gnu(W),
+    Bsd(W),
+    Json(W),
+    Jsonl(W),
+}
+
+pub struct ManifestWriter<W: Write> {
+    inner: WriterInner<W>,
+    /// no entry has been written yet, used for separator of json array
+    first: bool,
+    binary: bool,
+    outputfile: String,
+}
+
+fn write_text_line<W: Write>(w: &mut W, line: &str, outputfile: &str) -> Result<(), ApplicationError> {
+    match w.write_all(line.as_bytes()) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(ApplicationError::from_io(e, outputfile, "failed to write result")),
+    }
+}
+
+fn escape_path(path: &str) -> (bool, String) {
+    if !path.contains('\\') && !path.contains('\n') && !path.contains('\r') {
+        return (false, path.to_owned());
+    }
+    let escaped = path
+        .replace("\\", "\\\\")
+        .replace("\n", "\\n")
+        .replace("\r", "\\r");
+    (true, escaped)
+}
+
+fn unescape_path(path: &str) -> Option<String> {
+    let mut ret = String::new();
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            ret.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => ret.push('\\'),
+            Some('n') => ret.push('\n'),
+            Some('r') => ret.push('\r'),
+            _ => return None,
+        }
+    }
+    Some(ret)
+}
+
+impl<W: Write> ManifestWriter<W> {
+    /// create writer, metadata header is written first if specified.
+    /// csv also gets column names row after the header
+    pub fn new(
+        mut out_f: W,
+        format: ManifestFormat,
+        binary: bool,
+        outputfile: &str,
+        header: Option<&ManifestHeader>,
+    ) -> Result<ManifestWriter<W>, ApplicationError> {
+        if let Some(header) = header {
+            if format == ManifestFormat::Json || format == ManifestFormat::Jsonl {
+                return Err(ApplicationError::from_parameter(
+                    "header",
+                    "header is not supported by json format",
+                ));
+            }
+            write_text_line(&mut out_f, &header.to_line(), outputfile)?;
+        }
+        let inner = match format {
+            ManifestFormat::Csv => WriterInner::Csv(Box::new(csv::Writer::from_writer(out_f))),
+            ManifestFormat::Gnu => WriterInner::Gnu(out_f),
+            ManifestFormat::Bsd => WriterInner::Bsd(out_f),
+            ManifestFormat::Json => WriterInner::Json(out_f),
+            ManifestFormat::Jsonl => WriterInner::Jsonl(out_f),
+        };
+        let mut ret = ManifestWriter {
+            inner,
+            first: true,
+            binary,
+            outputfile: outputfile.to_owned(),
+        };
+        if let (Some(header), WriterInner::Csv(w)) = (header, &mut ret.inner) {
+            let mut record = vec!["path".to_owned()];
+            record.extend(header.algorithms.iter().map(|a| a.name()));
+            if let Err(e) = w.write_record(&record) {
+                return Err(ApplicationError::from_csv(
+                    e,
+                    format!("failed to write result({})", outputfile).as_str(),
+                ));
+            }
+        }
+        Ok(ret)
+    }
+    pub fn write_entry(
+        &mut self,
+        path: &str,
+        hashes: &[(Algorithm, String)],
+        info: &FileInfo,
+    ) -> Result<(), ApplicationError> {
+        let first = std::mem::replace(&mut self.first, false);
+        let is_array = matches!(self.inner, WriterInner::Json(_));
+        match &mut self.inner {
+            WriterInner::Csv(w) => {
+                let mut record = vec![path];
+                record.extend(hashes.iter().map(|(_, h)| h.as_str()));
+                match w.write_record(&record) {
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(ApplicationError::from_csv(
+                        e,
+                        format!("failed to write result({})", self.outputfile).as_str(),
+                    )),
+                }
+            }
+            WriterInner::Gnu(w) => {
+                if hashes.len() != 1 {
+                    return Err(ApplicationError::from_parameter(
+                        "format",
+                        "gnu format supports only one hash per file",
+                    ));
+                }
+                let (escaped, path) = escape_path(path);
+                let line = format!(
+                    "{}{} {}{}\n",
+                    if escaped { "\\" } else { "" },
+                    hashes[0].1,
+                    if self.binary { "*" } else { " " },
+                    path
+                );
+                write_text_line(w, &line, &self.outputfile)
+            }
+            WriterInner::Bsd(w) => {
+                let (escaped, path) = escape_path(path);
+                for (algorithm, hash) in hashes {
+                    let line = format!(
+                        "{}{} ({}) = {}\n",
+                        if escaped { "\\" } else { "" },
+                        algorithm.bsd_tag(),
+                        path,
+                        hash
+                    );
+                    write_text_line(w, &line, &self.outputfile)?;
+                }
+                Ok(())
+            }
+            WriterInner::Json(w) | WriterInner::Jsonl(w) => {
+                for (i, (algorithm, hash)) in hashes.iter().enumerate() {
+                    let entry = JsonEntry {
+                        path,
+                        algorithm: algorithm.to_spec(),
+                        hash,
+                        size: info.size,
+                        mtime: info.mtime.map(format_utc),
+                    };
+                    // serializing struct of strings and numbers never fails
+                    let entry = serde_json::to_string(&entry).expect("failed to serialize json");
+                    let line = if !is_array {
+                        format!("{}\n", entry)
+                    } else if first && i == 0 {
+                        format!("[\n{}", entry)
+                    } else {
+                        format!(",\n{}", entry)
+                    };
+                    write_text_line(w, &line, &self.outputfile)?;
+                }
+                Ok(())
+            }
+        }
+    }
+    /// write trailer of the format, must be called after all entries are written
+    pub fn finish(&mut self) -> Result<(), ApplicationError> {
+        let trailer = if self.first { "[]\n" } else { "\n]\n" };
+        match &mut self.inner {
+            WriterInner::Json(w) => write_text_line(w, trailer, &self.outputfile),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn read_csv_manifest<R: Read>(
+    in_f: R,
+    inputfile: &str,
+    has_headers: bool,
+) -> Result<Vec<ManifestEntry>, ApplicationError> {
+    let mut in_f = csv::ReaderBuilder::new()
+        .has_headers(has_headers)
+        .flexible(true)
+        .from_reader(in_f);
+    let mut ret = Vec::new();
+    for record in in_f.records() {
+        match record {
+            Ok(v) => {
+                if v.len() < 2 {
+                    let line = v.position().map(|p| p.line() as usize).unwrap_or(0);
+                    return Err(ApplicationError::from_manifest(inputfile, line, "invalid checksum line"));
+                }
+                ret.push(ManifestEntry {
+                    path: v[0].to_owned(),
+                    hash: v[1].to_owned(),
+                    algorithm: None,
+                });
+                Ok(())
+            }
+            Err(e) => Err(ApplicationError::from_csv(
+                e,
+                format!("failed to read csv file({})", inputfile).as_str(),
+            )),
+        }?;
+    }
+    Ok(ret)
+}
+
+fn parse_gnu_line(line: &str) -> Option<ManifestEntry> {
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(v) => (true, v),
+        None => (false, line),
+    };
+    let sep = line.find(' ')?;
+    let hash = &line[0..sep];
+    let rest = &line[sep + 1..];
+    // binary('*') and text(' ') mode are hashed in the same way
+    let path = rest.strip_prefix('*').or_else(|| rest.strip_prefix(' '))?;
+    if hash.is_empty() || path.is_empty() {
+        return None;
+    }
+    let path = if escaped {
+        unescape_path(path)?
+    } else {
+        path.to_owned()
+    };
+    Some(ManifestEntry {
+        path,
+        hash: hash.to_owned(),
+        algorithm: None,
+    })
+}
+
+fn parse_bsd_line(line: &str) -> Option<ManifestEntry> {
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(v) => (true, v),
+        None => (false, line),
+    };
+    let tag_end = line.find(" (")?;
+    let hash_begin = line.rfind(") = ")?;
+    if hash_begin < tag_end + 2 {
+        return None;
+    }
+    let tag = &line[0..tag_end];
+    let path = &line[tag_end + 2..hash_begin];
+    let hash = &line[hash_begin + 4..];
+    if hash.is_empty() || path.is_empty() {
+        return None;
+    }
+    let algorithm = Algorithm::from_bsd_tag(tag, digestutil::guess_digest_length(hash)?)?;
+    let path = if escaped {
+        unescape_path(path)?
+    } else {
+        path.to_owned()
+    };
+    Some(ManifestEntry {
+        path,
+        hash: hash.to_owned(),
+        algorithm: Some(algorithm),
+    })
+}
+
+fn read_line_manifest<R: Read>(
+    in_f: R,
+    inputfile: &str,
+    parse_line: fn(&str) -> Option<ManifestEntry>,
+) -> Result<Vec<ManifestEntry>, ApplicationError> {
+    let in_f = BufReader::new(in_f);
+    let mut ret = Vec::new();
+    for (i, line) in in_f.lines().enumerate() {
+        let line = match line {
+            Ok(v) => Ok(v),
+            Err(e) => Err(ApplicationError::from_io(e, inputfile, "failed to read checksum file")),
+        }?;
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() {
+            continue;
+        }
+        match parse_line(line) {
+            Some(v) => ret.push(v),
+            None => {
+                return Err(ApplicationError::from_manifest(
+                    inputfile,
+                    i + 1,
+                    "invalid checksum line",
+                ))
+            }
+        }
+    }
+    Ok(ret)
+}
+
+fn json_to_entry(v: JsonManifestEntry) -> Option<ManifestEntry> {
+    let algorithm = match v.algorithm {
+        Some(spec) => Some(Algorithm::from_spec(&spec)?),
+        None => None,
+    };
+    Some(ManifestEntry {
+        path: v.path,
+        hash: v.hash,
+        algorithm,
+    })
+}
+
+fn read_json_manifest<R: Read>(in_f: R, inputfile: &str) -> Result<Vec<ManifestEntry>, ApplicationError> {
+    let entries: Vec<JsonManifestEntry> = match serde_json::from_reader(in_f) {
+        Ok(v) => Ok(v),
+        Err(e) => Err(ApplicationError::from_manifest(
+            inputfile,
+            e.line(),
+            format!("invalid json({})", e).as_str(),
+        )),
+    }?;
+    let mut ret = Vec::new();
+    for entry in entries {
+        match json_to_entry(entry) {
+            Some(v) => ret.push(v),
+            None => return Err(ApplicationError::from_manifest(inputfile, 0, "unknown algorithm")),
+        }
+    }
+    Ok(ret)
+}
+
+fn parse_jsonl_line(line: &str) -> Option<ManifestEntry> {
+    json_to_entry(serde_json::from_str(line).ok()?)
+}
+
+pub fn read_manifest<R: Read>(in_f: R, format: ManifestFormat, inputfile: &str) -> Result<Manifest, ApplicationError> {
+    let mut in_f = BufReader::new(in_f);
+    let mut firstline = String::new();
+    if let Err(e) = in_f.read_line(&mut firstline) {
+        return Err(ApplicationError::from_io(e, inputfile, "failed to read checksum file"));
+    }
+    let header = if firstline.starts_with(HEADER_PREFIX) {
+        match ManifestHeader::parse(firstline.trim_end_matches(['\r', '\n'])) {
+            Some(v) => Some(v),
+            None => return Err(ApplicationError::from_manifest(inputfile, 1, "invalid manifest header")),
+        }
+    } else {
+        None
+    };
+    if header.is_some() {
+        // keep line numbers, empty lines are skipped by readers
+        firstline = "\n".to_owned();
+    }
+    let in_f = std::io::Cursor::new(firstline.into_bytes()).chain(in_f);
+    let entries = match format {
+        ManifestFormat::Csv => read_csv_manifest(in_f, inputfile, header.is_some()),
+        ManifestFormat::Gnu => read_line_manifest(in_f, inputfile, parse_gnu_line),
+        ManifestFormat::Bsd => read_line_manifest(in_f, inputfile, parse_bsd_line),
+        ManifestFormat::Json => read_json_manifest(in_f, inputfile),
+        ManifestFormat::Jsonl => read_line_manifest(in_f, inputfile, parse_jsonl_line),
+    }?;
+    Ok(Manifest { header, entries })
+}
+
+fn specialize_path_separator(input: &str) -> String {
+    input.replace("/", std::path::MAIN_SEPARATOR.to_string().as_str())
+}
+
+/// join basepath and a manifest path, "-" is kept as stdin
+pub fn resolve_path(basepath: &str, inputfile: &str) -> Result<String, ApplicationError> {
+    if inputfile == "-" {
+        return Ok("-".to_owned());
+    }
+    let mut p = std::path::PathBuf::new();
+    p.push(basepath);
+    p.push(specialize_path_separator(inputfile));
+    match p.to_str() {
+        Some(v) => Ok(v.to_owned()),
+        None => Err(ApplicationError::from_parameter("filename", format!("filename combine error({}, {})", basepath, inputfile).as_str()))
+    }
+}
+
+/// hash the file listed in entry(relative to basepath) and compare with the expected hash
+pub fn check_entry(
+    basepath: &str,
+    entry: &ManifestEntry,
+    algorithm: &Algorithm,
+    options: &ReadOptions,
+) -> Result<(), ApplicationError> {
+    let filepath = resolve_path(basepath, &entry.path)?;
+    let mut d = algorithm.create_hasher()?;
+    digestutil::update_digest_from_path(&mut d, &filepath, options)?;
+    let actual = d.finalize_reset();
+    // expected hash can be any encoding, so compare decoded bytes
+    let expected = digestutil::decode_digest(&entry.hash);
+    let hashstr = digestutil::encode_as_expected(&expected, &actual);
+    if !digestutil::digest_prefix_matches(&entry.hash, &algorithm.name()) {
+        let message = format!("algorithm prefix of expected hash is not {}", algorithm.name());
+        return Err(ApplicationError::from_check(&message, &entry.path, &filepath, &entry.hash, &hashstr));
+    }
+    if expected.iter().any(|(_, v)| *v == actual) {
+        return Ok(());
+    }
+    Err(ApplicationError::from_check("hash check failed", &entry.path, &filepath, &entry.hash, &hashstr))
+}
+
+/// result of checking a file
+#[derive(Debug)]
+pub enum CheckStatus {
+    Ok,
+    Failed(CheckError),
+    Missing,
+    Unreadable(ApplicationError),
+    /// exists in basepath but not listed in manifest
+    Extra,
+}
+
+impl CheckStatus {
+    pub fn from_result(result: Result<(), ApplicationError>) -> CheckStatus {
+        match result {
+            Ok(_) => CheckStatus::Ok,
+            Err(ApplicationError::Check(e)) => CheckStatus::Failed(e),
+            Err(ApplicationError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => CheckStatus::Missing,
+            Err(e) => CheckStatus::Unreadable(e),
+        }
+    }
+}
+
+/// options of check_manifest
+#[derive(Debug, Clone)]
+pub struct CheckOptions {
+    /// algorithm given by caller, None means it is detected from manifest
+    pub algorithm: Option<Algorithm>,
+    /// directory which relative paths in manifest are based on
+    pub basepath: String,
+    /// use base path in header instead of basepath, manifest may be untrusted so this is opt-in
+    pub use_header_basepath: bool,
+    pub read: ReadOptions,
+    /// number of files checked concurrently
+    pub jobs: usize,
+    /// report files which exist in basepath but are not listed in manifest
+    pub extra: Option<WalkOptions>,
+}
+
+impl CheckOptions {
+    pub fn new(algorithm: Option<Algorithm>, basepath: &str) -> CheckOptions {
+        CheckOptions {
+            algorithm,
+            basepath: basepath.to_owned(),
+            use_header_basepath: false,
+            read: ReadOptions::default(),
+            jobs: 1,
+            extra: None,
+        }
+    }
+}
+
+/// remove '.' and empty components, so that "./a//b" and "a/b" are treated as same file
+fn normalize_relative_path(path: &str) -> String {
+    path.split('/')
+        .filter(|v| !v.is_empty() && *v != ".")
+        .collect::<Vec<&str>>()
+        .join("/")
+}
+
+fn find_extra_files(
+    basepath: &str,
+    entries: &[ManifestEntry],
+    walkoptions: &WalkOptions,
+    inputfile: &str,
+) -> Result<Vec<String>, ApplicationError> {
+    let listed: std::collections::HashSet<String> = entries
+        .iter()
+        .map(|e| normalize_relative_path(&e.path))
+        .collect();
+    let manifest_path = std::fs::canonicalize(inputfile).ok();
+    let base = std::path::Path::new(basepath);
+    let mut ret = Vec::new();
+    for p in walkoptions.walk_dir(base)? {
+        let p = std::path::Path::new(&p);
+        if manifest_path.is_some() && std::fs::canonicalize(p).ok() == manifest_path {
+            continue;
+        }
+        let relative = match p.strip_prefix(base).ok().and_then(|v| v.to_str()) {
+            Some(v) => v.replace(std::path::MAIN_SEPARATOR, "/"),
+            None => return Err(ApplicationError::from_path_error(p, "failed to extract path string")),
+        };
+        let relative = normalize_relative_path(&relative);
+        if !listed.contains(&relative) {
+            ret.push(relative);
+        }
+    }
+    Ok(ret)
+}
+
+/// set algorithm in header to entries, configured algorithm must be the same as the header
+fn apply_header(
+    entries: &mut [ManifestEntry],
+    header: &ManifestHeader,
+    configured: Option<&Algorithm>,
+    inputfile: &str,
+) -> Result<(), ApplicationError> {
+    // explicit algorithm must agree with the header, algorithm is never switched silently
+    if let (Some(c), Some(h)) = (configured, header.algorithms.first()) {
+        if c.name() != h.name() {
+            return Err(ApplicationError::from_manifest(
+                inputfile,
+                1,
+                format!("algorithm in header({}) conflicts with subcommand({})", h.name(), c.name()).as_str(),
+            ));
+        }
+    }
+    for entry in entries.iter_mut() {
+        entry.algorithm = match &entry.algorithm {
+            // tag in BSD format doesn't have parameters
+            Some(v) => match header.algorithms.iter().find(|a| a.name() == v.name()) {
+                Some(h) => Some(v.merge_configured(h)?),
+                None => Some(v.clone()),
+            },
+            // first hash column is checked, so the first algorithm is used
+            None => header.algorithms.first().cloned(),
+        };
+    }
+    Ok(())
+}
+
+/// set algorithm detected from digest prefix or length to entries which have no algorithm
+pub fn detect_algorithms(entries: &mut [ManifestEntry]) -> Result<(), ApplicationError> {
+    for entry in entries.iter_mut().filter(|e| e.algorithm.is_none()) {
+        // SRI and Nix style digest tells algorithm by itself
+        if let (Some(name), _) = digestutil::split_digest_prefix(&entry.hash) {
+            entry.algorithm = Some(Algorithm::from_name(name, 0)?);
+            continue;
+        }
+        let names = match digestutil::guess_digest_length(&entry.hash) {
+            Some(length) => algorithm::names_from_digest_length(length),
+            None => Vec::new(),
+        };
+        match names.as_slice() {
+            [name] => entry.algorithm = Some(Algorithm::from_name(name, 0)?),
+            [] => {
+                return Err(ApplicationError::from_parameter(
+                    "algorithm",
+                    format!("cannot detect algorithm from digest({}, {})", entry.path, entry.hash).as_str(),
+                ))
+            }
+            _ => {
+                return Err(ApplicationError::from_parameter(
+                    "algorithm",
+                    format!(
+                        "digest length is ambiguous({}, candidates are {}), specify algorithm by subcommand",
+                        entry.path,
+                        names.join(", ")
+                    )
+                    .as_str(),
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// algorithm in manifest line or header takes precedence over configured one,
+/// but keyed algorithm is never replaced by unkeyed one
+fn resolve_algorithm(
+    entry: &ManifestEntry,
+    configured: Option<&Algorithm>,
+    inputfile: &str,
+) -> Result<Algorithm, ApplicationError> {
+    let algorithm = match (&entry.algorithm, configured) {
+        (Some(v), Some(c)) => match v.merge_configured(c) {
+            Ok(v) => v,
+            Err(ApplicationError::Parameter(e)) => {
+                return Err(ApplicationError::from_manifest(
+                    inputfile,
+                    0,
+                    format!("{}: {}", entry.path, e.message()).as_str(),
+                ))
+            }
+            Err(e) => return Err(e),
+        },
+        (Some(v), None) => v.clone(),
+        (None, Some(c)) => c.clone(),
+        (None, None) => unreachable!("algorithm must be detected for every entry"),
+    };
+    if algorithm.requires_key() {
+        return Err(ApplicationError::from_manifest(
+            inputfile,
+            0,
+            format!("{}: key required for {}", entry.path, algorithm.name()).as_str(),
+        ));
+    }
+    Ok(algorithm)
+}
+
+/// check every file in manifest, inputfile is the path of manifest used in errors and excluded from extra files.
+/// `report` is called with path, expected hash, algorithm and status in manifest order,
+/// and then for extra files with no expected hash and algorithm
+pub fn check_manifest<F>(
+    manifest: Manifest,
+    inputfile: &str,
+    options: &CheckOptions,
+    mut report: F,
+) -> Result<(), ApplicationError>
+where
+    F: FnMut(&str, Option<&str>, Option<&Algorithm>, CheckStatus),
+{
+    if manifest.entries.is_empty() {
+        // truncated or wrong format manifest must not pass
+        return Err(ApplicationError::from_manifest(inputfile, 0, "no properly formatted checksum lines found"));
+    }
+    let configured = options.algorithm.as_ref();
+    let mut entries = manifest.entries;
+    let mut basepath = options.basepath.as_str();
+    match &manifest.header {
+        Some(header) => {
+            apply_header(&mut entries, header, configured, inputfile)?;
+            if options.use_header_basepath {
+                basepath = &header.basepath;
+            }
+        }
+        None if options.use_header_basepath => {
+            return Err(ApplicationError::from_manifest(inputfile, 1, "no header to read base path from"))
+        }
+        None => {}
+    }
+    if configured.is_none() {
+        detect_algorithms(&mut entries)?;
+    }
+    // resolved before hashing, so that no entry is checked without the configured key
+    let algorithms = entries
+        .iter()
+        .map(|entry| resolve_algorithm(entry, configured, inputfile))
+        .collect::<Result<Vec<Algorithm>, ApplicationError>>()?;
+    let targets: Vec<(&ManifestEntry, &Algorithm)> = entries.iter().zip(algorithms.iter()).collect();
+    parallel::for_each_ordered(
+        &targets,
+        options.jobs,
+        |(entry, algorithm)| CheckStatus::from_result(check_entry(basepath, entry, algorithm, &options.read)),
+        |(entry, algorithm), status| {
+            report(&entry.path, Some(&entry.hash), Some(algorithm), status);
+            Ok(())
+        },
+    )?;
+    if let Some(walkoptions) = &options.extra {
+        for extra in find_extra_files(basepath, &entries, walkoptions, inputfile)? {
+            report(&extra, None, None, CheckStatus::Extra);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// sha2-256 of "hello\n"
+    const HELLO_SHA256: &str = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+
+    /// temporary directory with a.txt, removed on drop
+    struct TestDir(String);
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn create_basepath(name: &str) -> TestDir {
+        let dir = std::env::temp_dir().join(format!("hast-test-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.txt"), b"hello\n").unwrap();
+        TestDir(dir.to_str().unwrap().to_owned())
+    }
+
+    fn read(text: &str, format: ManifestFormat) -> Manifest {
+        read_manifest(text.as_bytes(), format, "test").unwrap()
+    }
+
+    fn run_check(manifest: Manifest, options: &CheckOptions) -> Result<Vec<String>, ApplicationError> {
+        let mut ret = Vec::new();
+        check_manifest(manifest, "test", options, |path, _, _, status| {
+            ret.push(format!("{}: {:?}", path, status))
+        })?;
+        Ok(ret)
+    }
+
+    #[test]
+    fn check_manifest_reports_each_entry() {
+        let basepath = create_basepath("report");
+        let text = format!("{}  a.txt\n{}  missing.txt\n", HELLO_SHA256, HELLO_SHA256);
+        let options = CheckOptions::new(Some(Algorithm::Sha256), &basepath.0);
+        let ret = run_check(read(&text, ManifestFormat::Gnu), &options).unwrap();
+        assert_eq!(ret, vec!["a.txt: Ok".to_owned(), "missing.txt: Missing".to_owned()]);
+    }
+
+    #[test]
+    fn truncated_manifest_fails() {
+        assert!(read_manifest("a.txt\n".as_bytes(), ManifestFormat::Csv, "test").is_err());
+        let options = CheckOptions::new(Some(Algorithm::Sha256), ".");
+        assert!(run_check(read("", ManifestFormat::Gnu), &options).is_err());
+        assert!(run_check(read("", ManifestFormat::Csv), &options).is_err());
+    }
+
+    #[test]
+    fn unkeyed_manifest_is_not_checked_with_key() {
+        let basepath = create_basepath("downgrade");
+        let hmac = Algorithm::Sha256.with_hmac(b"secret".to_vec()).unwrap();
+        // plain hash of the file would pass if the key were dropped
+        let bsd = format!("SHA256 (a.txt) = {}\n", HELLO_SHA256);
+        let options = CheckOptions::new(Some(hmac), &basepath.0);
+        assert!(run_check(read(&bsd, ManifestFormat::Bsd), &options).is_err());
+        let mut manifest = read(&format!("a.txt,{}\n", HELLO_SHA256), ManifestFormat::Csv);
+        manifest.header = Some(ManifestHeader {
+            version: String::new(),
+            created: String::new(),
+            basepath: basepath.0.clone(),
+            algorithms: vec![Algorithm::Md5],
+        });
+        assert!(run_check(manifest, &options).is_err());
+    }
+
+    #[test]
+    fn hmac_manifest_requires_key() {
+        let basepath = create_basepath("hmac");
+        let bsd = format!("HMAC-SHA256 (a.txt) = {}\n", HELLO_SHA256);
+        let options = CheckOptions::new(None, &basepath.0);
+        assert!(run_check(read(&bsd, ManifestFormat::Bsd), &options).is_err());
+        let options = CheckOptions::new(Some(Algorithm::Sha256), &basepath.0);
+        assert!(run_check(read(&bsd, ManifestFormat::Bsd), &options).is_err());
+    }
+
+    #[test]
+    fn check_entry_fails_for_hmac_without_key() {
+        let basepath = create_basepath("entry");
+        let bsd = format!("HMAC-SHA256 (a.txt) = {}\n", HELLO_SHA256);
+        let manifest = read(&bsd, ManifestFormat::Bsd);
+        let entry = &manifest.entries[0];
+        let algorithm = entry.algorithm.as_ref().unwrap();
+        assert!(check_entry(&basepath.0, entry, algorithm, &ReadOptions::default()).is_err());
+    }
+
+    #[test]
+    fn header_basepath_is_opt_in() {
+        let basepath = create_basepath("header");
+        let mut manifest = read(&format!("a.txt,{}\n", HELLO_SHA256), ManifestFormat::Csv);
+        manifest.header = Some(ManifestHeader {
+            version: String::new(),
+            created: String::new(),
+            basepath: basepath.0.clone(),
+            algorithms: vec![Algorithm::Sha256],
+        });
+        let mut options = CheckOptions::new(None, "/nonexistent-hast-dir");
+        assert_eq!(run_check(manifest.clone(), &options).unwrap(), vec!["a.txt: Missing".to_owned()]);
+        options.use_header_basepath = true;
+        assert_eq!(run_check(manifest.clone(), &options).unwrap(), vec!["a.txt: Ok".to_owned()]);
+        // header algorithm conflicts with the configured one
+        let options = CheckOptions::new(Some(Algorithm::Md5), &basepath.0);
+        assert!(run_check(manifest, &options).is_err());
+    }
+}
@@ -0,0 +1,62 @@
+use super::error::ApplicationError;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+
+/// number of worker threads, 0 means number of available cores
+pub fn resolve_jobs(jobs: usize) -> usize {
+    if jobs != 0 {
+        return jobs;
+    }
+    match std::thread::available_parallelism() {
+        Ok(v) => v.get(),
+        Err(_) => 1,
+    }
+}
+
+/// apply `f` to every item with `jobs` worker threads,
+/// and pass the items with their results to `sink` in the same order as `items`
+pub fn for_each_ordered<T, R, F, S>(items: &[T], jobs: usize, f: F, mut sink: S) -> Result<(), ApplicationError>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+    S: FnMut(&T, R) -> Result<(), ApplicationError>,
+{
+    if jobs <= 1 || items.len() <= 1 {
+        for item in items {
+            sink(item, f(item))?;
+        }
+        return Ok(());
+    }
+    let next = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..jobs.min(items.len()) {
+            let tx = tx.clone();
+            let next = &next;
+            let f = &f;
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                if i >= items.len() {
+                    break;
+                }
+                // receiver is dropped when sink failed, so stop working
+                if tx.send((i, f(&items[i]))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+        let mut pending = BTreeMap::new();
+        let mut expected = 0;
+        for (i, result) in rx {
+            pending.insert(i, result);
+            while let Some(result) = pending.remove(&expected) {
+                sink(&items[expected], result)?;
+                expected += 1;
+            }
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,112 @@
+use super::algorithm::Algorithm;
+use super::calc;
+use super::digestutil::{Encoding, ReadOptions};
+use super::error::ApplicationError;
+use clap::ArgMatches;
+
+/// algorithms allowed in Subresource Integrity, weakest first
+const SRI_ALGORITHMS: &[(&str, Algorithm)] = &[
+    ("sha256", Algorithm::Sha256),
+    ("sha384", Algorithm::Sha384),
+    ("sha512", Algorithm::Sha512),
+];
+
+pub const SRI_ALGORITHM_NAMES: &[&str] = &["sha256", "sha384", "sha512"];
+
+fn sri_name(algorithm: &Algorithm) -> &'static str {
+    SRI_ALGORITHMS
+        .iter()
+        .find(|(_, a)| a == algorithm)
+        .map(|(name, _)| *name)
+        .expect("only sha2 algorithms are used in SRI")
+}
+
+/// parse SRI like `sha384-xxx sha512-yyy` and return digests of the strongest algorithm.
+/// unsupported algorithms and options(`?opt`) are ignored, same as browsers
+fn parse_sri(sri: &str) -> Result<(Algorithm, Vec<Vec<u8>>), ApplicationError> {
+    let mut strongest: Option<usize> = None;
+    let mut digests = Vec::new();
+    for token in sri.split_whitespace() {
+        let (name, value) = match token.split_once('-') {
+            Some(v) => v,
+            None => continue,
+        };
+        let strength = match SRI_ALGORITHMS.iter().position(|(v, _)| *v == name) {
+            Some(v) => v,
+            None => continue,
+        };
+        let value = value.split('?').next().unwrap_or("");
+        let digest = match Encoding::Base64.decode(value) {
+            Some(v) => Ok(v),
+            None => Err(ApplicationError::from_parameter(
+                "verify",
+                format!("invalid base64 in SRI({})", token).as_str(),
+            )),
+        }?;
+        if strongest.map(|v| v < strength).unwrap_or(true) {
+            strongest = Some(strength);
+            digests.clear();
+        }
+        if strongest == Some(strength) {
+            digests.push(digest);
+        }
+    }
+    match strongest {
+        Some(v) => Ok((SRI_ALGORITHMS[v].1.clone(), digests)),
+        None => Err(ApplicationError::from_parameter(
+            "verify",
+            format!("no supported algorithm in SRI({})", sri).as_str(),
+        )),
+    }
+}
+
+fn verify_sri(matches: &ArgMatches, sri: &str) -> Result<(), ApplicationError> {
+    let (algorithm, expected) = parse_sri(sri)?;
+    let algorithms = [algorithm];
+    let mut ok = 0;
+    let mut failed = 0;
+    for inputfile in calc::expand_input_files(matches)? {
+        let (hashes, _) = calc::calc_file(&inputfile, &algorithms, Encoding::Base64, &ReadOptions::default())?;
+        let actual = Encoding::Base64.decode(&hashes[0].1);
+        if expected.iter().any(|v| Some(v) == actual.as_ref()) {
+            ok += 1;
+            println!("{}: OK", inputfile);
+        } else {
+            failed += 1;
+            println!("{}: FAILED", inputfile);
+        }
+    }
+    if failed != 0 {
+        return Err(ApplicationError::from_check_summary(ok, failed, 0, 0, 0));
+    }
+    Ok(())
+}
+
+pub fn do_sri(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    if let Some(sri) = matches.value_of("verify") {
+        return verify_sri(matches, sri);
+    }
+    let mut algorithms = Vec::new();
+    if let Some(vals) = matches.values_of("algorithm") {
+        for name in vals {
+            match SRI_ALGORITHMS.iter().find(|(v, _)| *v == name) {
+                Some((_, a)) => algorithms.push(a.clone()),
+                None => {
+                    return Err(ApplicationError::from_parameter(
+                        "algorithm",
+                        format!("Unknown algorithm({})", name).as_str(),
+                    ))
+                }
+            }
+        }
+    }
+    for inputfile in calc::expand_input_files(matches)? {
+        let (hashes, _) = calc::calc_file(&inputfile, &algorithms, Encoding::Base64, &ReadOptions::default())?;
+        let integrity: Vec<String> = hashes
+            .iter()
+            .map(|(a, h)| format!("{}-{}", sri_name(a), h))
+            .collect();
+        println!("{}  {}", integrity.join(" "), inputfile);
+    }
+    Ok(())
+}
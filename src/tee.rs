@@ -0,0 +1,99 @@
+use super::algorithm::Algorithm;
+use super::calc;
+use super::digestutil::Encoding;
+use super::error::ApplicationError;
+use super::ioutil;
+use super::manifest::{FileInfo, ManifestFormat, ManifestWriter};
+use clap::ArgMatches;
+use std::io::{Read, Write};
+
+/// copy stdin to stdout unchanged, and write the digest of the copied bytes to hash-out
+fn do_tee(matches: &ArgMatches, algorithm: Algorithm) -> Result<(), ApplicationError> {
+    let algorithm = calc::apply_hmac(matches, vec![algorithm])?.remove(0);
+    let hashout = matches.value_of("hash-out").unwrap_or("-");
+    if hashout == "-" {
+        return Err(ApplicationError::from_parameter(
+            "hash-out",
+            "stdout is used for the passed through data",
+        ));
+    }
+    let name = matches.value_of("name").unwrap_or("-");
+    let format = ManifestFormat::from_name(matches.value_of("format").unwrap_or("csv"))?;
+    let encoding = Encoding::from_name(matches.value_of("encoding").unwrap_or("hex"))?;
+    // open the hash file first, so that the input is not consumed if it cannot be created
+    let out_f = ioutil::create_file_for_write(hashout)?;
+    let mut d = algorithm.create_hasher()?;
+    let stdin = std::io::stdin();
+    let mut in_f = stdin.lock();
+    let stdout = std::io::stdout();
+    let mut passthrough = stdout.lock();
+    let mut buf: Vec<u8> = vec![0u8; 65536];
+    let mut size: u64 = 0;
+    loop {
+        let n = match in_f.read(&mut buf) {
+            Ok(v) => Ok(v),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => Err(ApplicationError::from_io(e, "-", "failed to read input")),
+        }?;
+        if n == 0 {
+            break;
+        }
+        d.update(&buf[0..n]);
+        if let Err(e) = passthrough.write_all(&buf[0..n]) {
+            return Err(ApplicationError::from_io(e, "-", "failed to write output"));
+        }
+        size += n as u64;
+    }
+    if let Err(e) = passthrough.flush() {
+        return Err(ApplicationError::from_io(e, "-", "failed to write output"));
+    }
+    let hash = encoding.encode(&d.finalize_reset());
+    let mut out_f = ManifestWriter::new(out_f, format, matches.is_present("binary"), hashout, None)?;
+    out_f.write_entry(name, &[(algorithm, hash)], &FileInfo { size: Some(size), mtime: None })?;
+    out_f.finish()
+}
+
+pub fn do_tee_sha1(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_tee(matches, Algorithm::Sha1)
+}
+
+pub fn do_tee_sha2(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    let bitlength = matches.value_of("length").unwrap_or("256");
+    do_tee(matches, Algorithm::from_sha2_length(bitlength)?)
+}
+
+pub fn do_tee_md5(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_tee(matches, Algorithm::Md5)
+}
+
+pub fn do_tee_sha3(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    let bitlength = matches.value_of("length").unwrap_or("256");
+    do_tee(matches, Algorithm::from_sha3_length(bitlength)?)
+}
+
+pub fn do_tee_shake(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    let bitlength = matches.value_of("length").unwrap_or("128");
+    let outlength = super::do_parse::<usize>(matches.value_of("outputlength").unwrap_or("128"))?;
+    do_tee(matches, Algorithm::from_shake_length(bitlength, outlength)?)
+}
+
+pub fn do_tee_blake2(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_tee(matches, calc::blake2_algorithm(matches)?)
+}
+
+pub fn do_tee_blake3(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_tee(matches, calc::blake3_algorithm(matches)?)
+}
+
+pub fn do_tee_crc(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    let algorithm = matches.value_of("algorithm").unwrap_or("crc32");
+    do_tee(matches, Algorithm::from_crc_variant(algorithm)?)
+}
+
+pub fn do_tee_adler32(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_tee(matches, Algorithm::Adler32)
+}
+
+pub fn do_tee_xxhash(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_tee(matches, calc::xxhash_algorithm(matches)?)
+}
@@ -0,0 +1,89 @@
+use super::algorithm::Algorithm;
+use super::calc;
+use super::digestutil;
+use super::error::ApplicationError;
+use clap::ArgMatches;
+use subtle::{Choice, ConstantTimeEq};
+
+fn do_verify(matches: &ArgMatches, algorithm: Algorithm) -> Result<(), ApplicationError> {
+    let algorithm = calc::apply_hmac(matches, vec![algorithm])?.remove(0);
+    let inputfile = matches.value_of("file").unwrap_or("-");
+    let expect = matches.value_of("expect").unwrap_or("");
+    if !digestutil::digest_prefix_matches(expect, &algorithm.name()) {
+        return Err(ApplicationError::from_parameter(
+            "expect",
+            format!("algorithm prefix of expected hash is not {}({})", algorithm.name(), expect).as_str(),
+        ));
+    }
+    let expected = digestutil::decode_digest(expect);
+    if expected.is_empty() {
+        return Err(ApplicationError::from_parameter(
+            "expect",
+            format!("expected hash is not decodable in any encoding({})", expect).as_str(),
+        ));
+    }
+    let options = calc::read_options_from_matches(matches)?;
+    let mut d = algorithm.create_hasher()?;
+    digestutil::update_digest_from_path(&mut d, inputfile, &options)?;
+    let actual = d.finalize_reset();
+    // compare with every decoded candidate without short circuit, so that timing doesn't leak the digest
+    let matched = expected
+        .iter()
+        .fold(Choice::from(0), |acc, (_, v)| acc | v.as_slice().ct_eq(&actual));
+    if !bool::from(matched) {
+        return Err(ApplicationError::from_check(
+            "hash mismatch",
+            inputfile,
+            inputfile,
+            expect,
+            &digestutil::encode_as_expected(&expected, &actual),
+        ));
+    }
+    println!("{}: OK", inputfile);
+    Ok(())
+}
+
+pub fn do_verify_sha1(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_verify(matches, Algorithm::Sha1)
+}
+
+pub fn do_verify_sha2(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    let bitlength = matches.value_of("length").unwrap_or("256");
+    do_verify(matches, Algorithm::from_sha2_length(bitlength)?)
+}
+
+pub fn do_verify_md5(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_verify(matches, Algorithm::Md5)
+}
+
+pub fn do_verify_sha3(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    let bitlength = matches.value_of("length").unwrap_or("256");
+    do_verify(matches, Algorithm::from_sha3_length(bitlength)?)
+}
+
+pub fn do_verify_shake(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    let bitlength = matches.value_of("length").unwrap_or("128");
+    let outlength = super::do_parse::<usize>(matches.value_of("outputlength").unwrap_or("128"))?;
+    do_verify(matches, Algorithm::from_shake_length(bitlength, outlength)?)
+}
+
+pub fn do_verify_blake2(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_verify(matches, calc::blake2_algorithm(matches)?)
+}
+
+pub fn do_verify_blake3(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_verify(matches, calc::blake3_algorithm(matches)?)
+}
+
+pub fn do_verify_crc(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    let algorithm = matches.value_of("algorithm").unwrap_or("crc32");
+    do_verify(matches, Algorithm::from_crc_variant(algorithm)?)
+}
+
+pub fn do_verify_adler32(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_verify(matches, Algorithm::Adler32)
+}
+
+pub fn do_verify_xxhash(matches: &ArgMatches) -> Result<(), ApplicationError> {
+    do_verify(matches, calc::xxhash_algorithm(matches)?)
+}
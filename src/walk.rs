@@ -0,0 +1,165 @@
+use super::error::ApplicationError;
+use std::path::Path;
+
+/// per-directory ignore file which is specific to hast, same syntax as .gitignore
+const HAST_IGNORE_FILENAME: &str = ".hastignore";
+
+/// options of directory traversal, patterns are matched against the path relative to the directory
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    /// walk_dir fails if this is false
+    pub recursive: bool,
+    pub includes: Vec<glob::Pattern>,
+    pub excludes: Vec<glob::Pattern>,
+    pub max_depth: Option<usize>,
+    pub follow_symlinks: bool,
+    pub one_file_system: bool,
+    /// skip files ignored by .gitignore, .ignore and .hastignore
+    pub respect_ignore: bool,
+}
+
+pub fn parse_patterns<'a, I>(patterns: I) -> Result<Vec<glob::Pattern>, ApplicationError>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut ret = Vec::new();
+    for v in patterns {
+        match glob::Pattern::new(v) {
+            Ok(p) => ret.push(p),
+            Err(e) => {
+                return Err(ApplicationError::from_glob_pattern_error(
+                    e,
+                    format!("failed to parse glob({})", v).as_str(),
+                ))
+            }
+        }
+    }
+    Ok(ret)
+}
+
+fn relative_path<'a>(root: &Path, path: &'a Path) -> &'a Path {
+    path.strip_prefix(root).unwrap_or(path)
+}
+
+/// whether the walk error is caused by a symlink whose target does not exist
+fn is_broken_link(e: &ignore::Error) -> bool {
+    match e {
+        ignore::Error::WithPath { path, .. } => {
+            path.symlink_metadata().map(|m| m.file_type().is_symlink()).unwrap_or(false) && path.metadata().is_err()
+        }
+        ignore::Error::WithDepth { err, .. } => is_broken_link(err),
+        _ => false,
+    }
+}
+
+impl WalkOptions {
+    /// options for listing all files under a tree, used to find files not listed in manifest
+    pub fn for_tree(includes: Vec<glob::Pattern>, respect_ignore: bool) -> WalkOptions {
+        WalkOptions {
+            recursive: true,
+            includes,
+            excludes: Vec::new(),
+            max_depth: None,
+            follow_symlinks: false,
+            one_file_system: false,
+            respect_ignore,
+        }
+    }
+    fn is_included(&self, relative: &Path) -> bool {
+        self.includes.is_empty() || self.includes.iter().any(|p| p.matches_path(relative))
+    }
+    /// whether the file(not directory) should be hashed
+    pub fn accepts_file(&self, path: &Path) -> bool {
+        !self.excludes.iter().any(|p| p.matches_path(path)) && self.is_included(path)
+    }
+    /// list files under the directory in sorted order,
+    /// include and exclude patterns are matched against the path relative to the directory
+    pub fn walk_dir(&self, dir: &Path) -> Result<Vec<String>, ApplicationError> {
+        if !self.recursive {
+            // command line mistake, not an I/O error
+            return Err(ApplicationError::from_parameter(
+                "recursive",
+                format!("input is a directory, use --recursive to hash files in it({})", dir.display()).as_str(),
+            ));
+        }
+        let mut builder = ignore::WalkBuilder::new(dir);
+        builder
+            .standard_filters(false)
+            .follow_links(self.follow_symlinks)
+            .same_file_system(self.one_file_system)
+            .max_depth(self.max_depth)
+            .sort_by_file_name(|a, b| a.cmp(b));
+        if self.respect_ignore {
+            builder
+                .git_ignore(true)
+                .git_exclude(true)
+                .ignore(true)
+                .parents(true)
+                .require_git(false)
+                .add_custom_ignore_filename(HAST_IGNORE_FILENAME);
+        }
+        let excludes = self.excludes.clone();
+        let respect_ignore = self.respect_ignore;
+        let root = dir.to_path_buf();
+        builder.filter_entry(move |e| {
+            // never prune the root even if it matches exclude pattern
+            if e.depth() == 0 {
+                return true;
+            }
+            // git never tracks its own directory, so follow that when ignore files are respected
+            if respect_ignore && e.file_name() == ".git" {
+                return false;
+            }
+            let relative = relative_path(&root, e.path());
+            !excludes.iter().any(|p| p.matches_path(relative))
+        });
+        let mut ret = Vec::new();
+        for entry in builder.build() {
+            let entry = match entry {
+                Ok(v) => Ok(v),
+                // following a broken link fails, it is skipped as well as when not following links
+                Err(e) if is_broken_link(&e) => continue,
+                Err(e) => Err(ApplicationError::from_walk(e, format!("failed to walk directory({})", dir.display()).as_str())),
+            }?;
+            // is_file follows symlink, so that links to directory and broken links are skipped,
+            // and fifos, sockets and devices are never opened for hashing
+            if !entry.path().is_file() {
+                continue;
+            }
+            if !self.is_included(relative_path(dir, entry.path())) {
+                continue;
+            }
+            match entry.path().to_str() {
+                Some(v) => ret.push(v.to_owned()),
+                None => return Err(ApplicationError::from_path_error(entry.path(), "failed to extract path string")),
+            }
+        }
+        Ok(ret)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walk_dir_returns_only_regular_files() {
+        let dir = std::env::temp_dir().join(format!("hast-test-walk-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("a.txt"), b"a").unwrap();
+        std::os::unix::fs::symlink("a.txt", dir.join("link")).unwrap();
+        std::os::unix::fs::symlink("nowhere", dir.join("dangling")).unwrap();
+        let socket = std::os::unix::net::UnixListener::bind(dir.join("sub").join("socket")).unwrap();
+        let mut options = WalkOptions::for_tree(Vec::new(), false);
+        for follow_symlinks in &[false, true] {
+            options.follow_symlinks = *follow_symlinks;
+            let files = options.walk_dir(&dir).unwrap();
+            let names: Vec<&str> = files.iter().map(|f| relative_path(&dir, Path::new(f)).to_str().unwrap()).collect();
+            assert_eq!(names, vec!["a.txt", "link"]);
+        }
+        drop(socket);
+        options.recursive = false;
+        assert_eq!(options.walk_dir(&dir).unwrap_err().exit_code(), crate::error::EXIT_PARAMETER);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}